@@ -0,0 +1,70 @@
+//对比每个敌人单独A*寻路和共享流场的开销
+use bracket_pathfinding::prelude::{a_star_search, Point};
+use bracket_random::prelude::RandomNumberGenerator;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use roguelike_tutorial::{
+    flow::FlowMaps,
+    map::{new_map_rooms_and_corridors, Map, TileType},
+};
+
+fn setup(monster_count: usize) -> (Map, Point, Vec<usize>) {
+    let mut map = new_map_rooms_and_corridors(&mut RandomNumberGenerator::seeded(1));
+    map.populate();
+
+    let (x, y) = map.rooms[0].center();
+    let player = Point::new(x, y);
+
+    let floors: Vec<usize> = map
+        .tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, tile)| **tile == TileType::Floor)
+        .map(|(idx, _)| idx)
+        .collect();
+
+    let monsters = (0..monster_count)
+        .map(|i| floors[(i * 7919) % floors.len()])
+        .collect();
+
+    (map, player, monsters)
+}
+
+fn chase(c: &mut Criterion) {
+    let mut group = c.benchmark_group("chase_player");
+
+    for monster_count in [10, 100, 200] {
+        let (map, player, monsters) = setup(monster_count);
+        let player_idx = map.xy_idx(player.x, player.y);
+
+        group.bench_with_input(
+            BenchmarkId::new("a_star_per_monster", monster_count),
+            &monsters,
+            |b, monsters| {
+                b.iter(|| {
+                    for idx in monsters.iter() {
+                        let path = a_star_search(*idx, player_idx, &map);
+                        black_box(path.steps.get(1));
+                    }
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("shared_flow_map", monster_count),
+            &monsters,
+            |b, monsters| {
+                b.iter(|| {
+                    let flow_maps = FlowMaps::new(&map, player);
+                    for idx in monsters.iter() {
+                        black_box(flow_maps.chase_step(&map, *idx));
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, chase);
+criterion_main!(benches);
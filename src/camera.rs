@@ -0,0 +1,133 @@
+use bevy::{input::mouse::MouseWheel, prelude::*};
+use bevy_egui::EguiContexts;
+
+use crate::{
+    consts::SPRITE_SIZE,
+    input::{Action, ActionInput},
+    loading::MainCamera,
+    map::{Map, MapInstance},
+    player::PlayerPosition,
+    AppState, GameState,
+};
+
+//进入游戏时的缩放，小于1时放大
+pub const CAMERA_DEFAULT_SCALE: f32 = 0.5;
+pub const CAMERA_MIN_SCALE: f32 = 0.25;
+pub const CAMERA_MAX_SCALE: f32 = 2.0;
+
+//每次缩放的倍数
+pub const CAMERA_ZOOM_STEP: f32 = 1.25;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), reset_zoom);
+
+        app.add_systems(Update, (camera_zoom,).run_if(in_state(GameState::Playing)));
+
+        app.add_systems(
+            Update,
+            (camera_follow,)
+                .after(camera_zoom)
+                .run_if(in_state(AppState::InGame)),
+        );
+
+        app.add_systems(OnExit(AppState::InGame), reset_camera);
+    }
+}
+
+fn reset_zoom(mut q_camera: Query<&mut OrthographicProjection, With<MainCamera>>) {
+    for mut projection in q_camera.iter_mut() {
+        projection.scale = CAMERA_DEFAULT_SCALE;
+    }
+}
+
+fn reset_camera(
+    mut q_camera: Query<(&mut Transform, &mut OrthographicProjection), With<MainCamera>>,
+) {
+    for (mut transform, mut projection) in q_camera.iter_mut() {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+        projection.scale = 1.0;
+    }
+}
+
+//缩放按键或者鼠标滚轮缩放
+fn camera_zoom(
+    actions: ActionInput,
+    mut mouse_wheel_er: EventReader<MouseWheel>,
+    mut q_camera: Query<&mut OrthographicProjection, With<MainCamera>>,
+    mut contexts: EguiContexts,
+) {
+    let mut zoom = 0.0;
+
+    if actions.just_pressed(Action::ZoomIn) {
+        zoom += 1.0;
+    }
+
+    if actions.just_pressed(Action::ZoomOut) {
+        zoom -= 1.0;
+    }
+
+    //滚轮在egui窗口和面板上时用来滚动窗口，不缩放地图
+    let over_ui = contexts.ctx_mut().is_pointer_over_area();
+
+    for event in mouse_wheel_er.read() {
+        if !over_ui {
+            zoom += event.y.signum();
+        }
+    }
+
+    if zoom == 0.0 {
+        return;
+    }
+
+    for mut projection in q_camera.iter_mut() {
+        projection.scale = (projection.scale / CAMERA_ZOOM_STEP.powf(zoom))
+            .clamp(CAMERA_MIN_SCALE, CAMERA_MAX_SCALE);
+    }
+}
+
+//镜头跟着玩家，到了地图边缘就停住，地图比屏幕小时放在正中间
+fn camera_follow(
+    mut q_camera: Query<(&mut Transform, &OrthographicProjection), With<MainCamera>>,
+    q_map: Query<&Transform, (With<MapInstance>, Without<MainCamera>)>,
+    player_position: Option<Res<PlayerPosition>>,
+    map: Option<Res<Map>>,
+) {
+    let (Some(player_position), Some(map)) = (player_position, map) else {
+        return;
+    };
+
+    let Ok(map_transform) = q_map.get_single() else {
+        return;
+    };
+
+    let tile = Vec2::new(SPRITE_SIZE[0] as f32, SPRITE_SIZE[1] as f32);
+    let origin = map_transform.translation.truncate();
+
+    //精灵以格子中心为原点
+    let min = origin - tile / 2.0;
+    let max = min + Vec2::new(map.width as f32, map.height as f32) * tile;
+
+    let target = origin + Vec2::new(player_position.0.x as f32, player_position.0.y as f32) * tile;
+
+    for (mut transform, projection) in q_camera.iter_mut() {
+        let half_view = projection.area.half_size();
+
+        let x = clamp_axis(target.x, min.x, max.x, half_view.x);
+        let y = clamp_axis(target.y, min.y, max.y, half_view.y);
+
+        transform.translation.x = x;
+        transform.translation.y = y;
+    }
+}
+
+fn clamp_axis(target: f32, min: f32, max: f32, half_view: f32) -> f32 {
+    if max - min <= half_view * 2.0 {
+        (min + max) / 2.0
+    } else {
+        target.clamp(min + half_view, max - half_view)
+    }
+}
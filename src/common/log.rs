@@ -0,0 +1,141 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use super::GameTurn;
+
+//日志最多保存的条数，超过后丢掉最早的
+pub const GAME_LOG_CAPACITY: usize = 500;
+
+//日志分类，日志窗口可以按分类过滤
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LogCategory {
+    Combat,
+    Item,
+    System,
+}
+
+impl LogCategory {
+    pub const ALL: [LogCategory; 3] = [LogCategory::Combat, LogCategory::Item, LogCategory::System];
+}
+
+impl ToString for LogCategory {
+    fn to_string(&self) -> String {
+        match *self {
+            LogCategory::Combat => format!("Combat"),
+            LogCategory::Item => format!("Item"),
+            LogCategory::System => format!("System"),
+        }
+    }
+}
+
+//一段文字的用途，界面按用途上色
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogStyle {
+    Plain,
+    Name,
+    Number,
+    Good,
+    Bad,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogSpan {
+    pub text: String,
+    pub style: LogStyle,
+}
+
+//一条日志，由多段不同颜色的文字组成
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub turn: u64,
+    pub category: LogCategory,
+    pub spans: Vec<LogSpan>,
+}
+
+impl LogEntry {
+    //回合数在加入GameLog时填写
+    pub fn new(category: LogCategory) -> Self {
+        LogEntry {
+            turn: 0,
+            category,
+            spans: vec![],
+        }
+    }
+
+    pub fn combat() -> Self {
+        LogEntry::new(LogCategory::Combat)
+    }
+
+    pub fn item() -> Self {
+        LogEntry::new(LogCategory::Item)
+    }
+
+    pub fn system() -> Self {
+        LogEntry::new(LogCategory::System)
+    }
+
+    pub fn span(mut self, text: impl ToString, style: LogStyle) -> Self {
+        self.spans.push(LogSpan {
+            text: text.to_string(),
+            style,
+        });
+        self
+    }
+
+    pub fn text(self, text: impl ToString) -> Self {
+        self.span(text, LogStyle::Plain)
+    }
+
+    pub fn name(self, text: impl ToString) -> Self {
+        self.span(text, LogStyle::Name)
+    }
+
+    pub fn number(self, text: impl ToString) -> Self {
+        self.span(text, LogStyle::Number)
+    }
+
+    pub fn good(self, text: impl ToString) -> Self {
+        self.span(text, LogStyle::Good)
+    }
+
+    pub fn bad(self, text: impl ToString) -> Self {
+        self.span(text, LogStyle::Bad)
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct GameLog {
+    entries: VecDeque<LogEntry>,
+    //新日志记录的回合数
+    turn: u64,
+}
+
+impl GameLog {
+    pub fn add(&mut self, mut entry: LogEntry) {
+        entry.turn = self.turn;
+
+        if self.entries.len() >= GAME_LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back(entry);
+    }
+
+    //从旧到新的全部日志
+    pub fn entries(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter()
+    }
+
+    //最近的几条日志，从旧到新
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &LogEntry> {
+        self.entries
+            .iter()
+            .skip(self.entries.len().saturating_sub(count))
+    }
+}
+
+//每帧开始时记下当前回合，这一帧写的日志都算在这个回合
+pub fn sync_log_turn(game_turn: Res<GameTurn>, mut log: ResMut<GameLog>) {
+    log.turn = game_turn.0;
+}
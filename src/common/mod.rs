@@ -1,25 +1,33 @@
+mod log;
 mod state_machine;
 
 use crate::{
     consts::SPRITE_SIZE,
-    map::{Map, MapTile},
+    map::{Door, Map, MapTile},
     player::PlayerEntity,
     state::AppStateManager,
     theme::Theme,
+    trap::{Hidden, Trap},
     AppState,
 };
 use bevy::{prelude::*, utils::hashbrown::HashMap};
-use bracket_pathfinding::prelude::{field_of_view, Point};
+use bracket_pathfinding::prelude::{field_of_view, DistanceAlg, Point};
 use bracket_random::prelude::RandomNumberGenerator as BracketRandomNumberGenerator;
 
+pub use log::*;
 pub use state_machine::*;
 
 #[derive(Resource, Deref, DerefMut)]
 pub struct RandomNumberGenerator(BracketRandomNumberGenerator);
 
-#[derive(Resource, Default)]
-pub struct GameLog {
-    pub entries: Vec<String>,
+//回合计数，玩家每行动一次加一
+#[derive(Resource, Default, Debug, Deref)]
+pub struct GameTurn(pub u64);
+
+impl GameTurn {
+    pub fn next(&mut self) {
+        self.0 += 1;
+    }
 }
 
 #[derive(Component, Debug)]
@@ -37,6 +45,22 @@ pub struct WantsToMelee {
     pub target: Entity,
 }
 
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+#[component(storage = "SparseSet")]
+pub struct WantsToShoot {
+    pub target: Entity,
+}
+
+//远程武器，射程和弹药
+#[derive(Component, Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct RangedWeapon {
+    pub range: i32,
+    pub ammo: i32,
+    pub power: i32,
+}
+
 #[derive(Component, Debug, Reflect)]
 #[reflect(Component)]
 #[component(storage = "SparseSet")]
@@ -72,7 +96,7 @@ pub fn delete_the_dead(
             } else {
                 commands.entity(entity).despawn_recursive();
 
-                log.entries.push(format!("{} is dead", &name));
+                log.add(LogEntry::combat().name(name).bad(" is dead"));
             }
         }
     }
@@ -98,12 +122,19 @@ pub fn melee_combat(
     let mut damage_map: HashMap<Entity, Vec<i32>> = HashMap::default();
 
     for (wants_to_melee, parent, entity) in q_wants_to_melee.iter() {
-        let (active, active_name, _) = q_combat_stats.get(parent.get()).unwrap();
+        commands.entity(entity).despawn_recursive();
+
+        //攻击者或者目标可能已经被别人杀死
+        let Ok((active, active_name, _)) = q_combat_stats.get(parent.get()) else {
+            continue;
+        };
         if active.hp < 0 {
             continue;
         }
 
-        let (unactive, unactive_name, _) = q_combat_stats.get(wants_to_melee.target).unwrap();
+        let Ok((unactive, unactive_name, _)) = q_combat_stats.get(wants_to_melee.target) else {
+            continue;
+        };
         if unactive.hp < 0 {
             continue;
         }
@@ -111,15 +142,22 @@ pub fn melee_combat(
         let damage = i32::max(0, active.power - unactive.defense);
 
         if damage == 0 {
-            log.entries.push(format!(
-                "{} is unable to hurt {}",
-                active_name, unactive_name
-            ))
+            log.add(
+                LogEntry::combat()
+                    .name(active_name)
+                    .text(" is unable to hurt ")
+                    .name(unactive_name),
+            )
         } else {
-            log.entries.push(format!(
-                "{} hits {}, for {} hp.",
-                &active_name, &unactive_name, damage
-            ));
+            log.add(
+                LogEntry::combat()
+                    .name(active_name)
+                    .text(" hits ")
+                    .name(unactive_name)
+                    .text(", for ")
+                    .number(damage)
+                    .text(" hp."),
+            );
 
             if let Some(tmp_damages) = damage_map.get_mut(&wants_to_melee.target) {
                 tmp_damages.push(damage)
@@ -127,12 +165,106 @@ pub fn melee_combat(
                 damage_map.insert(wants_to_melee.target, vec![damage]);
             }
         }
+    }
+
+    for (entity, damages) in damage_map.into_iter() {
+        let (_, _, suffer_damage) = q_combat_stats.get_mut(entity).unwrap();
+
+        if let Some(mut suffer_damage) = suffer_damage {
+            suffer_damage.amount.extend_from_slice(&damages);
+        } else {
+            commands
+                .entity(entity)
+                .insert(SufferDamage { amount: damages });
+        }
+    }
+}
+
+pub fn ranged_combat(
+    mut commands: Commands,
+    q_wants_to_shoot: Query<(&WantsToShoot, &Parent, Entity)>,
+    mut q_weapon: Query<(&mut RangedWeapon, &Position)>,
+    mut q_combat_stats: Query<(&CombatStats, &Name, &Position, Option<&mut SufferDamage>)>,
+    map: Res<Map>,
+    mut log: ResMut<GameLog>,
+) {
+    let mut damage_map: HashMap<Entity, Vec<i32>> = HashMap::default();
 
+    for (wants_to_shoot, parent, entity) in q_wants_to_shoot.iter() {
         commands.entity(entity).despawn_recursive();
+
+        let Ok((active, active_name, _, _)) = q_combat_stats.get(parent.get()) else {
+            continue;
+        };
+        if active.hp < 0 {
+            continue;
+        }
+
+        let Ok((unactive, unactive_name, unactive_position, _)) =
+            q_combat_stats.get(wants_to_shoot.target)
+        else {
+            continue;
+        };
+        if unactive.hp < 0 {
+            continue;
+        }
+
+        let Ok((mut weapon, position)) = q_weapon.get_mut(parent.get()) else {
+            continue;
+        };
+
+        if weapon.ammo <= 0 {
+            log.add(
+                LogEntry::combat()
+                    .name(active_name)
+                    .text(" is out of ammunition."),
+            );
+            continue;
+        }
+
+        let start = Point::new(position.x, position.y);
+        let end = Point::new(unactive_position.x, unactive_position.y);
+
+        if DistanceAlg::Pythagoras.distance2d(start, end) > weapon.range as f32 {
+            continue;
+        }
+
+        if !map.has_line_of_fire(start, end) {
+            continue;
+        }
+
+        weapon.ammo -= 1;
+
+        let damage = i32::max(0, weapon.power - unactive.defense);
+
+        if damage == 0 {
+            log.add(
+                LogEntry::combat()
+                    .name(active_name)
+                    .text(" is unable to hurt ")
+                    .name(unactive_name),
+            )
+        } else {
+            log.add(
+                LogEntry::combat()
+                    .name(active_name)
+                    .text(" shoots ")
+                    .name(unactive_name)
+                    .text(", for ")
+                    .number(damage)
+                    .text(" hp."),
+            );
+
+            if let Some(tmp_damages) = damage_map.get_mut(&wants_to_shoot.target) {
+                tmp_damages.push(damage)
+            } else {
+                damage_map.insert(wants_to_shoot.target, vec![damage]);
+            }
+        }
     }
 
     for (entity, damages) in damage_map.into_iter() {
-        let (_, _, suffer_damage) = q_combat_stats.get_mut(entity).unwrap();
+        let (_, _, _, suffer_damage) = q_combat_stats.get_mut(entity).unwrap();
 
         if let Some(mut suffer_damage) = suffer_damage {
             suffer_damage.amount.extend_from_slice(&damages);
@@ -147,12 +279,20 @@ pub fn melee_combat(
 fn update_visibility(
     mut q_position: Query<(&mut Visibility, &Position, &mut Sprite, Entity)>,
     q_tiles: Query<&MapTile>,
+    q_doors: Query<&Door>,
+    q_traps: Query<Has<Hidden>, With<Trap>>,
     map: Res<Map>,
     theme: Res<Theme>,
 ) {
     for (mut visibility, pos, mut sprite, entity) in q_position.iter_mut() {
         let idx = map.xy_idx(pos.x, pos.y);
 
+        //没发现的陷阱不显示
+        if matches!(q_traps.get(entity), Ok(true)) {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
         if map.visible_tiles[idx] {
             *visibility = Visibility::Visible;
 
@@ -169,6 +309,11 @@ fn update_visibility(
 
                 let glyph = theme.revealed_tile_to_render(tile);
                 sprite.color = glyph.color;
+            } else if (q_doors.get(entity).is_ok() || q_traps.get(entity).is_ok())
+                && map.revealed_tiles[idx]
+            {
+                //已经发现的门和陷阱一直显示
+                *visibility = Visibility::Visible;
             } else {
                 *visibility = Visibility::Hidden;
             }
@@ -224,18 +369,24 @@ impl Plugin for CommonPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Position>();
         app.register_type::<WantsToMelee>();
+        app.register_type::<WantsToShoot>();
+        app.register_type::<RangedWeapon>();
         app.register_type::<SufferDamage>();
         app.insert_resource(RandomNumberGenerator(BracketRandomNumberGenerator::new()));
 
+        app.add_systems(
+            First,
+            sync_log_turn.run_if(resource_exists_and_changed::<GameTurn>),
+        );
+
         app.add_systems(
             Update,
             (
                 keep_position,
                 update_viewshed,
                 update_visibility,
-                melee_combat,
-                apply_damage,
-                delete_the_dead,
+                //近战和远程的伤害都写进SufferDamage后再结算，chain会在中间执行commands
+                (melee_combat, ranged_combat, apply_damage, delete_the_dead).chain(),
             )
                 .run_if(in_state(AppState::InGame)),
         );
@@ -7,3 +7,11 @@ pub struct Idle;
 #[derive(Debug, Component, Clone)]
 #[component(storage = "SparseSet")]
 pub struct Follow;
+
+#[derive(Debug, Component, Clone)]
+#[component(storage = "SparseSet")]
+pub struct Flee;
+
+#[derive(Debug, Component, Clone)]
+#[component(storage = "SparseSet")]
+pub struct Search;
@@ -6,3 +6,9 @@ pub const MAP_Z_INDEX: f32 = -10.0;
 pub const PLAYER_Z_INDEX: f32 = 10.0;
 pub const ENEMY_Z_INDEX: f32 = 9.0;
 pub const ITEM_Z_INDEX: f32 = 8.0;
+pub const DOOR_Z_INDEX: f32 = 7.0;
+pub const TRAP_Z_INDEX: f32 = 6.0;
+
+//地图大小，可以比窗口大，镜头会跟着玩家移动
+pub const MAP_WIDTH: i32 = 80;
+pub const MAP_HEIGHT: i32 = 50;
@@ -0,0 +1,176 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts};
+use bracket_random::prelude::RandomNumberGenerator as BracketRandomNumberGenerator;
+
+use crate::{
+    common::{Position, RandomNumberGenerator},
+    map::{random_map, Map, MapTile},
+    spawner::ThemeContext,
+    state::AppStateManager,
+    theme::Theme,
+    AppState,
+};
+
+//自动播放时每一步的间隔
+pub const MAP_GEN_STEP_SECONDS: f32 = 0.3;
+
+//回放地图生成过程的状态
+#[derive(Resource)]
+pub struct MapGenViewer {
+    pub map: Map,
+    pub seed: u64,
+    pub index: usize,
+    pub paused: bool,
+    pub timer: Timer,
+    pub map_entity: Entity,
+}
+
+impl MapGenViewer {
+    fn snapshot_count(&self) -> usize {
+        self.map.history.len()
+    }
+}
+
+pub struct MapGenPlugin;
+
+impl Plugin for MapGenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::MapGen), setup_map_gen);
+
+        app.add_systems(
+            Update,
+            (map_gen_input, map_gen_play, show_snapshot, map_gen_ui)
+                .chain()
+                .run_if(in_state(AppState::MapGen)),
+        );
+
+        app.add_systems(OnExit(AppState::MapGen), clear_map_gen);
+    }
+}
+
+fn spawn_viewer(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    seed: u64,
+) -> MapGenViewer {
+    let map = random_map(&mut BracketRandomNumberGenerator::seeded(seed));
+
+    let map_entity = map.spawn_tiles(
+        commands,
+        &theme_context.texture_assets,
+        &mut theme_context.layout_assets,
+        &theme_context.theme,
+    );
+
+    MapGenViewer {
+        map,
+        seed,
+        index: 0,
+        paused: false,
+        timer: Timer::from_seconds(MAP_GEN_STEP_SECONDS, TimerMode::Repeating),
+        map_entity,
+    }
+}
+
+fn setup_map_gen(
+    mut commands: Commands,
+    mut theme_context: ThemeContext,
+    mut rng: ResMut<RandomNumberGenerator>,
+) {
+    let seed = rng.next_u64();
+    let viewer = spawn_viewer(&mut commands, &mut theme_context, seed);
+
+    commands.insert_resource(viewer);
+}
+
+fn clear_map_gen(mut commands: Commands, viewer: Res<MapGenViewer>) {
+    commands.entity(viewer.map_entity).despawn_recursive();
+    commands.remove_resource::<MapGenViewer>();
+}
+
+//空格暂停，左右方向键单步，R换一个种子重新生成，Esc回到菜单
+fn map_gen_input(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut viewer: ResMut<MapGenViewer>,
+    mut theme_context: ThemeContext,
+    mut rng: ResMut<RandomNumberGenerator>,
+    mut app_state_manager: AppStateManager,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        app_state_manager.back_to_menu();
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        viewer.paused = !viewer.paused;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        viewer.paused = true;
+        viewer.index = (viewer.index + 1).min(viewer.snapshot_count().saturating_sub(1));
+    }
+
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        viewer.paused = true;
+        viewer.index = viewer.index.saturating_sub(1);
+    }
+
+    if keyboard_input.just_pressed(KeyCode::KeyR) {
+        commands.entity(viewer.map_entity).despawn_recursive();
+
+        let seed = rng.next_u64();
+        *viewer = spawn_viewer(&mut commands, &mut theme_context, seed);
+    }
+}
+
+fn map_gen_play(mut viewer: ResMut<MapGenViewer>, time: Res<Time>) {
+    if viewer.paused {
+        return;
+    }
+
+    viewer.timer.tick(time.delta());
+
+    if viewer.timer.just_finished() && viewer.index + 1 < viewer.snapshot_count() {
+        viewer.index += 1;
+    }
+}
+
+fn show_snapshot(
+    viewer: Res<MapGenViewer>,
+    mut q_tiles: Query<(&Position, &mut TextureAtlas, &mut Sprite), With<MapTile>>,
+    theme: Res<Theme>,
+    mut shown: Local<Option<(u64, usize)>>,
+) {
+    //只在换了快照时刷新
+    if *shown == Some((viewer.seed, viewer.index)) {
+        return;
+    }
+
+    let Some(snapshot) = viewer.map.history.get(viewer.index) else {
+        return;
+    };
+
+    for (position, mut atlas, mut sprite) in q_tiles.iter_mut() {
+        let tile = snapshot[viewer.map.xy_idx(position.x, position.y)];
+        let glyph = theme.tile_to_render(tile);
+
+        atlas.index = glyph.index;
+        sprite.color = glyph.color;
+    }
+
+    *shown = Some((viewer.seed, viewer.index));
+}
+
+fn map_gen_ui(viewer: Res<MapGenViewer>, mut contexts: EguiContexts) {
+    egui::Window::new("Map Generation").show(contexts.ctx_mut(), |ui| {
+        ui.label(format!("Seed: {}", viewer.seed));
+        ui.label(format!(
+            "Snapshot: {} / {}",
+            viewer.index + 1,
+            viewer.snapshot_count()
+        ));
+        ui.label(if viewer.paused { "Paused" } else { "Playing" });
+        ui.label("Space: pause  Left/Right: step  R: new seed  Esc: menu");
+    });
+}
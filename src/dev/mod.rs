@@ -3,6 +3,10 @@ use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
 use bevy::prelude::*;
 
+mod map_gen;
+
+use map_gen::MapGenPlugin;
+
 pub struct DevPlugin;
 
 impl Plugin for DevPlugin {
@@ -11,6 +15,7 @@ impl Plugin for DevPlugin {
             FrameTimeDiagnosticsPlugin,
             LogDiagnosticsPlugin::default(),
             WorldInspectorPlugin::new(),
+            MapGenPlugin,
         ));
     }
 }
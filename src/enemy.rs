@@ -1,88 +1,725 @@
-use bevy::{ecs::system::EntityCommands, prelude::*};
-use bracket_pathfinding::prelude::{a_star_search, DistanceAlg, Point};
+use bevy::{
+    ecs::system::EntityCommands,
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bracket_pathfinding::prelude::{a_star_search, Algorithm2D, BaseMap, DistanceAlg, Point};
 use seldom_state::{prelude::StateMachine, trigger::IntoTrigger};
 
 use crate::{
-    common::{Follow, Idle, Position, Viewshed, WantsToMelee},
-    map::Map,
-    player::{PlayerEntity, PlayerPosition},
+    common::{
+        CombatStats, Flee, Follow, GameLog, Idle, LogEntry, Position, RandomNumberGenerator,
+        RangedWeapon, Search, Viewshed, WantsToMelee, WantsToShoot,
+    },
+    faction::{Faction, FactionTable, Reaction},
+    flow::FlowMaps,
+    map::{CanOpenDoors, Map, Mobility, MobilityQuery, OpenDoorEvent, Swimmer, TileType},
     GameState,
 };
 
-pub fn add_state_machine(commands: &mut EntityCommands, _enemy: EnemyType) {
+//到达目标最后出现的位置后继续搜索的回合数
+const SEARCH_TURNS: i32 = 8;
+
+//闲逛时原地停留的概率，1/n
+const WANDER_PAUSE_CHANCE: i32 = 3;
+
+//没有房间时随机找能走的格子作为目的地的尝试次数
+const WANDER_DESTINATION_TRIES: usize = 20;
+
+//空闲时群体成员与首领保持的距离
+const PACK_FOLLOW_DISTANCE: f32 = 2.5;
+
+pub fn add_state_machine(commands: &mut EntityCommands, enemy: EnemyType) {
     commands.insert((
         StateMachine::default()
-            .trans::<Idle, _>(look_player, Follow)
-            .trans::<Follow, _>(look_player.not(), Idle)
+            .trans::<Idle, _>(has_target, Follow)
+            .trans::<Idle, _>(should_flee, Flee)
+            .trans::<Follow, _>(should_flee, Flee)
+            .trans::<Follow, _>(has_target.not(), Search)
+            .trans::<Search, _>(has_target, Follow)
+            .trans::<Search, _>(search_exhausted, Idle)
+            .trans::<Flee, _>(recovered.or(cornered), Follow)
             .set_trans_logging(true),
         Idle,
         EnemyTimer::default(),
+        EnemyMemory::default(),
+        EnemyTarget::default(),
+        Wander::default(),
     ));
+
+    if let Some(flee_threshold) = enemy.flee_threshold() {
+        commands.insert(flee_threshold);
+    }
+
+    if enemy.can_open_doors() {
+        commands.insert(CanOpenDoors);
+    }
+
+    if enemy.can_swim() {
+        commands.insert(Swimmer);
+    }
+}
+
+fn search_exhausted(In(entity): In<Entity>, q_enemy: Query<&EnemyMemory, With<Enemy>>) -> bool {
+    if let Ok(memory) = q_enemy.get(entity) {
+        memory.last_seen.is_none() && memory.search_turns <= 0
+    } else {
+        true
+    }
 }
 
-fn look_player(
+//害怕对方或者生命值低于阈值，并且有路可逃
+fn should_flee(
     In(entity): In<Entity>,
-    q_enemy: Query<&Viewshed, With<Enemy>>,
-    player_position: Res<PlayerPosition>,
+    q_enemy: Query<
+        (
+            &CombatStats,
+            Option<&FleeThreshold>,
+            &Position,
+            &EnemyTarget,
+            MobilityQuery,
+            Has<Broken>,
+        ),
+        With<Enemy>,
+    >,
+    map: Res<Map>,
 ) -> bool {
-    if let Ok(viewshed) = q_enemy.get(entity) {
-        if viewshed
-            .visible_tiles
-            .contains(&Point::new(player_position.0.x, player_position.0.y))
-        {
-            return true;
-        } else {
-            return false;
+    let Ok((stats, threshold, position, target, movement, broken)) = q_enemy.get(entity) else {
+        return false;
+    };
+
+    let Some(threat) = target.threat() else {
+        return false;
+    };
+
+    let afraid = target.flee_from.is_some()
+        || broken
+        || threshold.map_or(false, |threshold| {
+            (stats.hp as f32) < stats.max_hp as f32 * threshold.flee
+        });
+
+    afraid && step_away(&map, position, threat, movement.mobility()).is_some()
+}
+
+fn recovered(
+    In(entity): In<Entity>,
+    q_enemy: Query<
+        (
+            &CombatStats,
+            Option<&FleeThreshold>,
+            &EnemyTarget,
+            Has<Broken>,
+        ),
+        With<Enemy>,
+    >,
+) -> bool {
+    if let Ok((stats, threshold, target, broken)) = q_enemy.get(entity) {
+        target.flee_from.is_none()
+            && !broken
+            && threshold.map_or(true, |threshold| {
+                stats.hp as f32 >= stats.max_hp as f32 * threshold.recover
+            })
+    } else {
+        true
+    }
+}
+
+//无路可逃
+fn cornered(
+    In(entity): In<Entity>,
+    q_enemy: Query<(&Position, &EnemyTarget, MobilityQuery), With<Enemy>>,
+    map: Res<Map>,
+) -> bool {
+    if let Ok((position, target, movement)) = q_enemy.get(entity) {
+        match target.threat() {
+            Some(threat) => step_away(&map, position, threat, movement.mobility()).is_none(),
+            None => false,
         }
     } else {
-        return false;
+        false
+    }
+}
+
+fn has_target(In(entity): In<Entity>, q_enemy: Query<&EnemyTarget, With<Enemy>>) -> bool {
+    if let Ok(target) = q_enemy.get(entity) {
+        target.attack.is_some()
+    } else {
+        false
+    }
+}
+
+//远离目标的一步，没有更远的格子时返回None
+fn step_away(map: &Map, position: &Position, target: Point, mobility: Mobility) -> Option<usize> {
+    let start = Point::new(position.x, position.y);
+    let current = DistanceAlg::Pythagoras.distance2d(start, target);
+
+    map.with_mobility(mobility)
+        .get_available_exits(map.xy_idx(position.x, position.y))
+        .into_iter()
+        .map(|(idx, _)| {
+            let point = map.index_to_point2d(idx);
+            (idx, DistanceAlg::Pythagoras.distance2d(point, target))
+        })
+        .filter(|(_, distance)| *distance > current)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(idx, _)| idx)
+}
+
+//移动到下一个格子，遇到关着的门时能开门的敌人先开门，其他敌人只能等着
+fn move_to(
+    map: &Map,
+    next: usize,
+    position: &mut Position,
+    viewshed: &mut Viewshed,
+    mobility: Mobility,
+    open_door_ew: &mut EventWriter<OpenDoorEvent>,
+) {
+    if let Some(door) = map.doors[next] {
+        if mobility.open_doors {
+            open_door_ew.send(OpenDoorEvent { door });
+        }
+
+        return;
+    }
+
+    position.x = next as i32 % map.width;
+    position.y = next as i32 / map.width;
+    viewshed.dirty = true;
+}
+
+//寻路的下一步
+fn path_step(map: &Map, from: usize, to: usize, mobility: Mobility) -> Option<usize> {
+    let path = a_star_search(from, to, &map.with_mobility(mobility));
+
+    if path.success && path.steps.len() > 1 {
+        Some(path.steps[1])
+    } else {
+        None
+    }
+}
+
+//目标周围离自己最近的空闲格子，用来包围目标
+fn surround_slot(
+    map: &Map,
+    start: Point,
+    target: Point,
+    claimed: &HashSet<usize>,
+    mobility: Mobility,
+) -> Option<usize> {
+    let mut slot: Option<(usize, f32)> = None;
+
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+
+            let point = Point::new(target.x + dx, target.y + dy);
+
+            if !map.in_bounds(point) {
+                continue;
+            }
+
+            let idx = map.xy_idx(point.x, point.y);
+
+            if !map.is_passable(idx, mobility) || claimed.contains(&idx) {
+                continue;
+            }
+
+            let distance = DistanceAlg::Pythagoras.distance2d(start, point);
+
+            if slot.map_or(true, |(_, nearest)| distance < nearest) {
+                slot = Some((idx, distance));
+            }
+        }
+    }
+
+    slot.map(|(idx, _)| idx)
+}
+
+//根据阵营反应表从视野内的生物中选出攻击目标和需要躲避的对象
+fn select_target(
+    mut q_enemy: Query<(Entity, &Faction, &Viewshed, &Position, &mut EnemyTarget), With<Enemy>>,
+    q_creature: Query<(Entity, &Faction, &Position), With<CombatStats>>,
+    faction_table: Res<FactionTable>,
+) {
+    for (entity, faction, viewshed, position, mut target) in q_enemy.iter_mut() {
+        let start = Point::new(position.x, position.y);
+
+        let mut attack: Option<(Entity, Point, f32)> = None;
+        let mut flee_from: Option<(Point, f32)> = None;
+
+        for (other, other_faction, other_position) in q_creature.iter() {
+            if other == entity {
+                continue;
+            }
+
+            let point = Point::new(other_position.x, other_position.y);
+
+            if !viewshed.visible_tiles.contains(&point) {
+                continue;
+            }
+
+            let distance = DistanceAlg::Pythagoras.distance2d(start, point);
+
+            match faction_table.reaction(*faction, *other_faction) {
+                Reaction::Attack => {
+                    if attack.map_or(true, |(_, _, nearest)| distance < nearest) {
+                        attack = Some((other, point, distance));
+                    }
+                }
+                Reaction::Flee => {
+                    if flee_from.map_or(true, |(_, nearest)| distance < nearest) {
+                        flee_from = Some((point, distance));
+                    }
+                }
+                Reaction::Ignore => {}
+            }
+        }
+
+        target.attack = attack.map(|(other, point, _)| (other, point));
+        target.flee_from = flee_from.map(|(point, _)| point);
     }
 }
 
 fn enemy_ai(
     mut commands: Commands,
     mut q_enemy: Query<
-        (&mut Viewshed, &mut Position, &Name, Entity, &mut EnemyTimer),
+        (
+            &mut Viewshed,
+            &mut Position,
+            &Name,
+            Entity,
+            &mut EnemyTimer,
+            &EnemyTarget,
+            Option<&RangedWeapon>,
+            Option<&KeepDistance>,
+            Option<&PackMember>,
+            MobilityQuery,
+        ),
         (With<Enemy>, With<Follow>),
     >,
-    player_position: Res<PlayerPosition>,
-    player_entity: Res<PlayerEntity>,
     mut map: ResMut<Map>,
+    flow_maps: Option<Res<FlowMaps>>,
+    mut open_door_ew: EventWriter<OpenDoorEvent>,
     time: Res<Time>,
 ) {
-    for (mut viewshed, mut position, name, entity, mut timer) in q_enemy.iter_mut() {
+    //群体成员已经占用的包围位置
+    let mut claimed_slots: HashSet<usize> = HashSet::default();
+
+    for (
+        mut viewshed,
+        mut position,
+        name,
+        entity,
+        mut timer,
+        target,
+        ranged_weapon,
+        keep_distance,
+        pack_member,
+        movement,
+    ) in q_enemy.iter_mut()
+    {
         timer.tick(time.delta());
 
         if !timer.just_finished() {
             continue;
         }
 
+        let Some((target_entity, target_point)) = target.attack else {
+            continue;
+        };
+
+        let mobility = movement.mobility();
+
         info!("{} shouts insults", name);
 
-        let distance = DistanceAlg::Pythagoras.distance2d(
-            Point::new(position.x, position.y),
-            Point::new(player_position.0.x, player_position.0.y),
-        );
+        let start = Point::new(position.x, position.y);
+        let distance = DistanceAlg::Pythagoras.distance2d(start, target_point);
 
-        if distance < 1.5 {
-            let player = player_entity.0.clone();
+        if let Some(weapon) = ranged_weapon {
+            if weapon.ammo > 0
+                && distance <= weapon.range as f32
+                && map.has_line_of_fire(start, target_point)
+            {
+                //离目标太近时先后退
+                if let Some(keep_distance) = keep_distance {
+                    if distance < keep_distance.0 {
+                        if let Some(idx) = step_away(&map, &position, target_point, mobility) {
+                            move_to(
+                                &map,
+                                idx,
+                                &mut position,
+                                &mut viewshed,
+                                mobility,
+                                &mut open_door_ew,
+                            );
 
+                            continue;
+                        }
+                    }
+                }
+
+                commands.entity(entity).with_children(|parent| {
+                    parent.spawn(WantsToShoot {
+                        target: target_entity,
+                    });
+                });
+
+                continue;
+            }
+        }
+
+        if distance < 1.5 {
             commands.entity(entity).with_children(|parent| {
-                parent.spawn(WantsToMelee { target: player });
+                parent.spawn(WantsToMelee {
+                    target: target_entity,
+                });
             });
 
-            return;
+            continue;
+        }
+
+        let idx = map.xy_idx(position.x, position.y);
+
+        //群体成员分散到目标周围不同的格子，而不是在走廊里排队
+        let slot_step = pack_member.and_then(|_| {
+            let slot = surround_slot(&map, start, target_point, &claimed_slots, mobility)?;
+            let step = path_step(&map, idx, slot, mobility)?;
+
+            claimed_slots.insert(slot);
+
+            Some(step)
+        });
+
+        //追击玩家时使用共享的流场，其他目标或者不会开门的敌人才单独寻路
+        let next = match (slot_step, flow_maps.as_ref()) {
+            (Some(step), _) => Some(step),
+            (None, Some(flow_maps))
+                if flow_maps.target == target_point && flow_maps.mobility == mobility =>
+            {
+                flow_maps.chase_step(&map, idx)
+            }
+            _ => {
+                //目标本身会阻挡格子，寻路时暂时放开
+                let target_idx = map.xy_idx(target_point.x, target_point.y);
+                let blocked = map.blocked[target_idx];
+                map.blocked[target_idx] = false;
+
+                let step = path_step(&map, idx, target_idx, mobility);
+
+                map.blocked[target_idx] = blocked;
+
+                step
+            }
+        };
+
+        if let Some(next) = next {
+            move_to(
+                &map,
+                next,
+                &mut position,
+                &mut viewshed,
+                mobility,
+                &mut open_door_ew,
+            );
+        }
+    }
+}
+
+//看到目标时记住目标的位置
+fn remember_target(mut q_enemy: Query<(&EnemyTarget, &mut EnemyMemory), With<Enemy>>) {
+    for (target, mut memory) in q_enemy.iter_mut() {
+        if let Some((_, point)) = target.attack {
+            memory.last_seen = Some(point);
+            memory.search_turns = SEARCH_TURNS;
+        }
+    }
+}
+
+//前往目标最后出现的位置，到达后随机游荡几个回合
+fn enemy_search(
+    mut q_enemy: Query<
+        (
+            &mut Viewshed,
+            &mut Position,
+            &mut EnemyMemory,
+            &mut EnemyTimer,
+            MobilityQuery,
+        ),
+        (With<Enemy>, With<Search>),
+    >,
+    map: Res<Map>,
+    mut rng: ResMut<RandomNumberGenerator>,
+    mut open_door_ew: EventWriter<OpenDoorEvent>,
+    time: Res<Time>,
+) {
+    for (mut viewshed, mut position, mut memory, mut timer, movement) in q_enemy.iter_mut() {
+        timer.tick(time.delta());
+
+        if !timer.just_finished() {
+            continue;
+        }
+
+        let idx = map.xy_idx(position.x, position.y);
+        let mobility = movement.mobility();
+
+        let next = match memory.last_seen {
+            Some(last_seen) => {
+                let step = path_step(&map, idx, map.xy_idx(last_seen.x, last_seen.y), mobility);
+
+                if step.is_none() {
+                    memory.last_seen = None;
+                }
+
+                step
+            }
+            None => {
+                memory.search_turns -= 1;
+
+                let exits = map.with_mobility(mobility).get_available_exits(idx);
+
+                if exits.is_empty() {
+                    None
+                } else {
+                    let roll = rng.roll_dice(1, exits.len() as i32) - 1;
+
+                    Some(exits[roll as usize].0)
+                }
+            }
+        };
+
+        if let Some(next) = next {
+            move_to(
+                &map,
+                next,
+                &mut position,
+                &mut viewshed,
+                mobility,
+                &mut open_door_ew,
+            );
+
+            if memory.last_seen == Some(Point::new(position.x, position.y)) {
+                memory.last_seen = None;
+            }
+        }
+    }
+}
+
+//闲逛的目的地，有房间时选随机房间里的一格，没有房间（WFC和预制地图）时选随机一个能走的格子
+fn wander_destination(
+    map: &Map,
+    rng: &mut RandomNumberGenerator,
+    mobility: Mobility,
+) -> Option<Point> {
+    if !map.rooms.is_empty() {
+        let room = &map.rooms[(rng.roll_dice(1, map.rooms.len() as i32) - 1) as usize];
+
+        let x = room.x1 + rng.roll_dice(1, i32::abs(room.x2 - room.x1));
+        let y = room.y1 + rng.roll_dice(1, i32::abs(room.y2 - room.y1));
+
+        return Some(Point::new(x, y));
+    }
+
+    for _ in 0..WANDER_DESTINATION_TRIES {
+        let x = rng.roll_dice(1, map.width - 2);
+        let y = rng.roll_dice(1, map.height - 2);
+
+        if map.is_passable(map.xy_idx(x, y), mobility) {
+            return Some(Point::new(x, y));
+        }
+    }
+
+    None
+}
+
+//空闲时在房间之间闲逛，群体成员跟着首领走
+fn enemy_wander(
+    mut set: ParamSet<(
+        Query<
+            (
+                &mut Viewshed,
+                &mut Position,
+                &mut Wander,
+                &mut EnemyTimer,
+                Option<&PackMember>,
+                MobilityQuery,
+            ),
+            (With<Enemy>, With<Idle>),
+        >,
+        Query<(Entity, &Position), With<PackLeader>>,
+    )>,
+    map: Res<Map>,
+    mut rng: ResMut<RandomNumberGenerator>,
+    mut open_door_ew: EventWriter<OpenDoorEvent>,
+    time: Res<Time>,
+) {
+    let leaders: HashMap<Entity, Point> = set
+        .p1()
+        .iter()
+        .map(|(entity, position)| (entity, Point::new(position.x, position.y)))
+        .collect();
+
+    let mut claimed_slots: HashSet<usize> = HashSet::default();
+
+    for (mut viewshed, mut position, mut wander, mut timer, pack_member, movement) in
+        set.p0().iter_mut()
+    {
+        timer.tick(time.delta());
+
+        if !timer.just_finished() {
+            continue;
+        }
+
+        let current = Point::new(position.x, position.y);
+        let mobility = movement.mobility();
+
+        if let Some(leader) = pack_member.and_then(|member| leaders.get(&member.leader)) {
+            if DistanceAlg::Pythagoras.distance2d(current, *leader) <= PACK_FOLLOW_DISTANCE {
+                continue;
+            }
+
+            let idx = map.xy_idx(position.x, position.y);
+
+            if let Some(slot) = surround_slot(&map, current, *leader, &claimed_slots, mobility) {
+                claimed_slots.insert(slot);
+
+                if let Some(next) = path_step(&map, idx, slot, mobility) {
+                    move_to(
+                        &map,
+                        next,
+                        &mut position,
+                        &mut viewshed,
+                        mobility,
+                        &mut open_door_ew,
+                    );
+                }
+            }
+
+            continue;
         }
 
-        let path = a_star_search(
-            map.xy_idx(position.x, position.y) as i32,
-            map.xy_idx(player_position.0.x, player_position.0.y) as i32,
-            &mut *map,
+        if rng.roll_dice(1, WANDER_PAUSE_CHANCE) == 1 {
+            continue;
+        }
+
+        if wander.destination.is_none() || wander.destination == Some(current) {
+            wander.destination = wander_destination(&map, &mut rng, mobility);
+        }
+
+        let Some(destination) = wander.destination else {
+            continue;
+        };
+
+        let step = path_step(
+            &map,
+            map.xy_idx(position.x, position.y),
+            map.xy_idx(destination.x, destination.y),
+            mobility,
         );
-        if path.success && path.steps.len() > 1 {
-            position.x = path.steps[1] as i32 % (map.width as i32);
-            position.y = path.steps[1] as i32 / (map.width as i32);
-            viewshed.dirty = true;
+
+        if let Some(next) = step {
+            move_to(
+                &map,
+                next,
+                &mut position,
+                &mut viewshed,
+                mobility,
+                &mut open_door_ew,
+            );
+        } else {
+            //路被挡住时换一个目的地
+            wander.destination = None;
+        }
+    }
+}
+
+//首领死亡后群体士气崩溃，成员看到威胁就逃跑
+fn pack_morale(
+    mut commands: Commands,
+    q_member: Query<(Entity, &PackMember)>,
+    q_leader: Query<(), With<PackLeader>>,
+    mut log: ResMut<GameLog>,
+) {
+    let mut broken = false;
+
+    for (entity, member) in q_member.iter() {
+        if q_leader.get(member.leader).is_ok() {
+            continue;
+        }
+
+        commands
+            .entity(entity)
+            .remove::<PackMember>()
+            .insert(Broken);
+
+        broken = true;
+    }
+
+    if broken {
+        log.add(LogEntry::combat().good("The war band loses heart as its leader falls!"));
+    }
+}
+
+//沿着Dijkstra地图远离威胁，逃跑时缓慢恢复生命
+fn enemy_flee(
+    mut q_enemy: Query<
+        (
+            &mut Viewshed,
+            &mut Position,
+            &mut CombatStats,
+            &mut EnemyTimer,
+            &EnemyTarget,
+            &EnemyMemory,
+            MobilityQuery,
+        ),
+        (With<Enemy>, With<Flee>),
+    >,
+    map: Res<Map>,
+    flow_maps: Option<Res<FlowMaps>>,
+    mut open_door_ew: EventWriter<OpenDoorEvent>,
+    time: Res<Time>,
+) {
+    //不是玩家的威胁或者移动能力不同时，按位置和移动能力计算一次流场
+    let mut threat_flow_maps: HashMap<(usize, Mobility), FlowMaps> = HashMap::default();
+
+    for (mut viewshed, mut position, mut stats, mut timer, target, memory, movement) in
+        q_enemy.iter_mut()
+    {
+        timer.tick(time.delta());
+
+        if !timer.just_finished() {
+            continue;
+        }
+
+        stats.hp = (stats.hp + 1).min(stats.max_hp);
+
+        let Some(threat) = target.threat().or(memory.last_seen) else {
+            continue;
+        };
+
+        let mobility = movement.mobility();
+
+        let flee_maps: &FlowMaps = match flow_maps.as_ref() {
+            Some(flow_maps) if flow_maps.target == threat && flow_maps.mobility == mobility => {
+                flow_maps
+            }
+            _ => threat_flow_maps
+                .entry((map.xy_idx(threat.x, threat.y), mobility))
+                .or_insert_with(|| FlowMaps::new(&map, threat, mobility)),
+        };
+
+        let idx = map.xy_idx(position.x, position.y);
+
+        if let Some(exit) = flee_maps.flee_step(&map, idx) {
+            move_to(
+                &map,
+                exit,
+                &mut position,
+                &mut viewshed,
+                mobility,
+                &mut open_door_ew,
+            );
         }
     }
 }
@@ -91,7 +728,37 @@ pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (enemy_ai,).run_if(in_state(GameState::Playing)));
+        app.add_systems(
+            Update,
+            (
+                pack_morale,
+                select_target,
+                remember_target,
+                enemy_ai,
+                enemy_flee,
+                enemy_search,
+                enemy_wander,
+                wading_slowdown,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+//在浅水里的敌人计时器只走一半，行动变慢
+fn wading_slowdown(
+    mut q_enemy: Query<(&Position, &mut EnemyTimer), With<Enemy>>,
+    map: Res<Map>,
+    time: Res<Time>,
+) {
+    for (position, mut timer) in q_enemy.iter_mut() {
+        if map.tiles[map.xy_idx(position.x, position.y)] != TileType::ShallowWater {
+            continue;
+        }
+
+        let elapsed = timer.elapsed().saturating_sub(time.delta() / 2);
+        timer.set_elapsed(elapsed);
     }
 }
 
@@ -104,11 +771,103 @@ impl Default for EnemyTimer {
     }
 }
 
+//敌人当前的攻击目标和需要躲避的对象
+#[derive(Debug, Component, Default)]
+pub struct EnemyTarget {
+    pub attack: Option<(Entity, Point)>,
+    pub flee_from: Option<Point>,
+}
+
+impl EnemyTarget {
+    //逃跑时远离的位置
+    pub fn threat(&self) -> Option<Point> {
+        self.flee_from.or(self.attack.map(|(_, point)| point))
+    }
+}
+
+//闲逛的目的地
+#[derive(Debug, Component, Default)]
+pub struct Wander {
+    pub destination: Option<Point>,
+}
+
+//敌人对目标的记忆
+#[derive(Debug, Component, Default)]
+pub struct EnemyMemory {
+    pub last_seen: Option<Point>,
+    pub search_turns: i32,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum EnemyType {
     G,
     O,
+    A,
+    S,
+    F,
 }
 
+impl EnemyType {
+    pub fn faction(&self) -> Faction {
+        match self {
+            EnemyType::G => Faction::Goblin,
+            EnemyType::O => Faction::Orc,
+            EnemyType::A => Faction::Kobold,
+            EnemyType::S => Faction::Vermin,
+            EnemyType::F => Faction::Animal,
+        }
+    }
+
+    pub fn can_open_doors(&self) -> bool {
+        match self {
+            EnemyType::G | EnemyType::O | EnemyType::A => true,
+            EnemyType::S | EnemyType::F => false,
+        }
+    }
+
+    pub fn can_swim(&self) -> bool {
+        matches!(self, EnemyType::F)
+    }
+
+    pub fn flee_threshold(&self) -> Option<FleeThreshold> {
+        match self {
+            EnemyType::G => Some(FleeThreshold {
+                flee: 0.3,
+                recover: 0.7,
+            }),
+            EnemyType::A => Some(FleeThreshold {
+                flee: 0.5,
+                recover: 0.8,
+            }),
+            EnemyType::O | EnemyType::S | EnemyType::F => None,
+        }
+    }
+}
+
+//生命值比例低于flee时逃跑，恢复到recover后重新追击
+#[derive(Debug, Component, Clone)]
+pub struct FleeThreshold {
+    pub flee: f32,
+    pub recover: f32,
+}
+
+//群体的首领
+#[derive(Debug, Component)]
+pub struct PackLeader;
+
+//群体成员，记录所属的首领
+#[derive(Debug, Component)]
+pub struct PackMember {
+    pub leader: Entity,
+}
+
+//首领死亡后士气崩溃的群体成员，不管生命值多少看到威胁就逃跑
+#[derive(Debug, Component)]
+pub struct Broken;
+
+//远程敌人与目标保持的距离
+#[derive(Debug, Component)]
+pub struct KeepDistance(pub f32);
+
 #[derive(Debug, Component)]
 pub struct Enemy;
@@ -0,0 +1,97 @@
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+
+//阵营
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq, Hash, Reflect)]
+#[reflect(Component)]
+pub enum Faction {
+    Player,
+    Goblin,
+    Orc,
+    Kobold,
+    Vermin,
+    Animal,
+}
+
+//一个阵营对另一个阵营的反应
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reaction {
+    Attack,
+    Ignore,
+    Flee,
+}
+
+//阵营反应表，没有记录的组合默认忽略
+#[derive(Debug, Resource)]
+pub struct FactionTable {
+    reactions: HashMap<(Faction, Faction), Reaction>,
+}
+
+impl FactionTable {
+    pub fn reaction(&self, faction: Faction, other: Faction) -> Reaction {
+        if faction == other {
+            return Reaction::Ignore;
+        }
+
+        self.reactions
+            .get(&(faction, other))
+            .copied()
+            .unwrap_or(Reaction::Ignore)
+    }
+
+    pub fn set_reaction(&mut self, faction: Faction, other: Faction, reaction: Reaction) {
+        self.reactions.insert((faction, other), reaction);
+    }
+}
+
+impl Default for FactionTable {
+    fn default() -> Self {
+        let mut table = FactionTable {
+            reactions: HashMap::default(),
+        };
+
+        for monster in [
+            Faction::Goblin,
+            Faction::Orc,
+            Faction::Kobold,
+            Faction::Vermin,
+        ] {
+            table.set_reaction(monster, Faction::Player, Reaction::Attack);
+            table.set_reaction(Faction::Player, monster, Reaction::Attack);
+        }
+
+        //兽人和地精是世仇
+        table.set_reaction(Faction::Goblin, Faction::Orc, Reaction::Attack);
+        table.set_reaction(Faction::Orc, Faction::Goblin, Reaction::Attack);
+
+        //动物是中立的，只会躲开危险
+        table.set_reaction(Faction::Animal, Faction::Player, Reaction::Flee);
+        table.set_reaction(Faction::Animal, Faction::Vermin, Reaction::Flee);
+        table.set_reaction(Faction::Vermin, Faction::Animal, Reaction::Attack);
+
+        table
+    }
+}
+
+//判断一个生物是不是玩家的敌人，被魅惑的同伴和中立的动物不算
+#[derive(SystemParam)]
+pub struct PlayerHostility<'w, 's> {
+    q_faction: Query<'w, 's, &'static Faction>,
+    faction_table: Res<'w, FactionTable>,
+}
+
+impl<'w, 's> PlayerHostility<'w, 's> {
+    pub fn is_hostile(&self, entity: Entity) -> bool {
+        self.q_faction.get(entity).map_or(false, |faction| {
+            self.faction_table.reaction(Faction::Player, *faction) == Reaction::Attack
+        })
+    }
+}
+
+pub struct FactionPlugin;
+
+impl Plugin for FactionPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Faction>();
+        app.init_resource::<FactionTable>();
+    }
+}
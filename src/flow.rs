@@ -0,0 +1,153 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use bevy::prelude::*;
+use bracket_pathfinding::prelude::{BaseMap, DijkstraMap, Point};
+
+use crate::{
+    common::GameTurn,
+    map::{Map, Mobility, MobilityMap},
+    player::PlayerPosition,
+    AppState, GameState,
+};
+
+//流场计算的最大距离
+pub const FLOW_MAX_DEPTH: f32 = 200.0;
+
+//逃跑地图的系数，绝对值大于1时逃跑会更倾向于开阔的区域而不是死胡同
+pub const FLEE_FACTOR: f32 = -1.2;
+
+//以玩家为起点的流场，每回合计算一次，移动能力相同的追击或逃跑的敌人共用
+#[derive(Resource)]
+pub struct FlowMaps {
+    pub target: Point,
+    pub mobility: Mobility,
+    pub chase: DijkstraMap,
+    pub flee: DijkstraMap,
+}
+
+impl FlowMaps {
+    pub fn new(map: &Map, target: Point, mobility: Mobility) -> Self {
+        let map = map.with_mobility(mobility);
+        let chase = build_chase_map(&map, target);
+        let flee = build_flee_map(&map, &chase);
+
+        FlowMaps {
+            target,
+            mobility,
+            chase,
+            flee,
+        }
+    }
+
+    //靠近目标的下一步，超出流场范围或者没有更近的格子时返回None
+    pub fn chase_step(&self, map: &Map, idx: usize) -> Option<usize> {
+        let exit =
+            DijkstraMap::find_lowest_exit(&self.chase, idx, &map.with_mobility(self.mobility))?;
+
+        if self.chase.map[exit] < self.chase.map[idx] {
+            Some(exit)
+        } else {
+            None
+        }
+    }
+
+    //远离目标的下一步，没有更好的格子时返回None
+    pub fn flee_step(&self, map: &Map, idx: usize) -> Option<usize> {
+        let exit =
+            DijkstraMap::find_lowest_exit(&self.flee, idx, &map.with_mobility(self.mobility))?;
+
+        if self.flee.map[exit] < self.flee.map[idx] {
+            Some(exit)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn build_chase_map(map: &MobilityMap, target: Point) -> DijkstraMap {
+    let start = map.map.xy_idx(target.x, target.y);
+
+    DijkstraMap::new(map.map.width, map.map.height, &[start], map, FLOW_MAX_DEPTH)
+}
+
+#[derive(PartialEq)]
+struct OpenNode {
+    cost: f32,
+    idx: usize,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    //BinaryHeap是最大堆，反过来比较得到最小堆
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+//把追击地图乘以FLEE_FACTOR作为初始值，再重新扩散一遍
+pub fn build_flee_map(map: &MobilityMap, chase: &DijkstraMap) -> DijkstraMap {
+    let mut flee = DijkstraMap::new_empty(map.map.width, map.map.height, FLOW_MAX_DEPTH);
+    let mut open = BinaryHeap::new();
+
+    for (idx, value) in chase.map.iter().enumerate() {
+        if *value < f32::MAX {
+            let cost = value * FLEE_FACTOR;
+
+            flee.map[idx] = cost;
+            open.push(OpenNode { cost, idx });
+        }
+    }
+
+    while let Some(OpenNode { cost, idx }) = open.pop() {
+        if cost > flee.map[idx] {
+            continue;
+        }
+
+        for (exit, exit_cost) in map.get_available_exits(idx) {
+            let new_cost = cost + exit_cost;
+
+            if new_cost < flee.map[exit] {
+                flee.map[exit] = new_cost;
+                open.push(OpenNode {
+                    cost: new_cost,
+                    idx: exit,
+                });
+            }
+        }
+    }
+
+    flee
+}
+
+fn update_flow_maps(mut commands: Commands, map: Res<Map>, player_position: Res<PlayerPosition>) {
+    commands.insert_resource(FlowMaps::new(&map, player_position.0, Mobility::default()));
+}
+
+fn clear_flow_maps(mut commands: Commands) {
+    commands.remove_resource::<FlowMaps>();
+}
+
+pub struct FlowMapPlugin;
+
+impl Plugin for FlowMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            update_flow_maps.run_if(
+                in_state(GameState::Playing).and_then(
+                    resource_exists_and_changed::<PlayerPosition>
+                        .or_else(resource_exists_and_changed::<GameTurn>),
+                ),
+            ),
+        );
+
+        app.add_systems(OnExit(AppState::InGame), clear_flow_maps);
+    }
+}
@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+
+use crate::{
+    common::{GameLog, GameTurn, LogEntry, SufferDamage},
+    player::Player,
+    GameState,
+};
+
+//每个饥饿阶段持续的回合数
+pub const HUNGER_STATE_DURATION: i32 = 200;
+
+//饥饿时每回合受到的伤害
+pub const STARVING_DAMAGE: i32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum HungerState {
+    WellFed,
+    Normal,
+    Hungry,
+    Starving,
+}
+
+impl ToString for HungerState {
+    fn to_string(&self) -> String {
+        match *self {
+            HungerState::WellFed => format!("Well Fed"),
+            HungerState::Normal => format!("Normal"),
+            HungerState::Hungry => format!("Hungry"),
+            HungerState::Starving => format!("Starving"),
+        }
+    }
+}
+
+//饥饿时钟，每回合减少，归零时进入下一个阶段
+#[derive(Component, Debug, Reflect)]
+#[reflect(Component)]
+pub struct HungerClock {
+    pub state: HungerState,
+    pub duration: i32,
+}
+
+impl Default for HungerClock {
+    fn default() -> Self {
+        HungerClock {
+            state: HungerState::WellFed,
+            duration: HUNGER_STATE_DURATION,
+        }
+    }
+}
+
+impl HungerClock {
+    //吃东西后回到吃饱状态
+    pub fn eat(&mut self) {
+        self.state = HungerState::WellFed;
+        self.duration = HUNGER_STATE_DURATION;
+    }
+}
+
+fn hunger_clock(
+    mut commands: Commands,
+    mut q_hunger: Query<(&mut HungerClock, Entity, Option<&mut SufferDamage>), With<Player>>,
+    mut log: ResMut<GameLog>,
+) {
+    for (mut clock, entity, suffer_damage) in q_hunger.iter_mut() {
+        //饥饿阶段每回合都会受到伤害
+        if clock.state != HungerState::Starving {
+            clock.duration -= 1;
+
+            if clock.duration > 0 {
+                continue;
+            }
+        }
+
+        match clock.state {
+            HungerState::WellFed => {
+                clock.state = HungerState::Normal;
+                clock.duration = HUNGER_STATE_DURATION;
+
+                log.add(LogEntry::system().text("You are no longer well fed."));
+            }
+            HungerState::Normal => {
+                clock.state = HungerState::Hungry;
+                clock.duration = HUNGER_STATE_DURATION;
+
+                log.add(LogEntry::system().bad("You are hungry."));
+            }
+            HungerState::Hungry => {
+                clock.state = HungerState::Starving;
+                clock.duration = 0;
+
+                log.add(LogEntry::system().bad("You are starving!"));
+            }
+            HungerState::Starving => {
+                log.add(
+                    LogEntry::system()
+                        .bad("Your hunger pangs are getting painful! ")
+                        .text("You suffer ")
+                        .number(STARVING_DAMAGE)
+                        .text(" hp damage."),
+                );
+
+                if let Some(mut suffer_damage) = suffer_damage {
+                    suffer_damage.amount.push(STARVING_DAMAGE);
+                } else {
+                    commands.entity(entity).insert(SufferDamage {
+                        amount: vec![STARVING_DAMAGE],
+                    });
+                }
+            }
+        }
+    }
+}
+
+pub struct HungerPlugin;
+
+impl Plugin for HungerPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<HungerClock>();
+
+        app.add_systems(
+            Update,
+            (hunger_clock,).run_if(
+                in_state(GameState::Playing).and_then(resource_exists_and_changed::<GameTurn>),
+            ),
+        );
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::{input::InputSystem, prelude::*, window::PrimaryWindow};
+use bevy_egui::{egui, EguiContexts, EguiInput, EguiSet};
+
+use super::{Action, DirectionRepeat, MoveInput};
+use crate::{AppState, GameState};
+
+//摇杆推过这个值才算按下
+pub const GAMEPAD_STICK_THRESHOLD: f32 = 0.5;
+
+impl Action {
+    //手柄按键，方向键和摇杆由GamepadMove处理
+    pub fn gamepad_buttons(&self) -> &'static [GamepadButtonType] {
+        match self {
+            Action::Wait => &[GamepadButtonType::RightThumb],
+            Action::Shoot => &[GamepadButtonType::West],
+            Action::Rest => &[GamepadButtonType::LeftTrigger],
+            Action::Explore => &[GamepadButtonType::RightTrigger],
+            Action::Disarm => &[GamepadButtonType::Select],
+            Action::OpenInventory => &[GamepadButtonType::North],
+            Action::Confirm => &[GamepadButtonType::South],
+            Action::Cancel => &[GamepadButtonType::East, GamepadButtonType::Start],
+            Action::ZoomIn => &[GamepadButtonType::RightTrigger2],
+            Action::ZoomOut => &[GamepadButtonType::LeftTrigger2],
+            _ => &[],
+        }
+    }
+}
+
+//手柄方向键和左摇杆的移动，按住时先等待一会再按固定间隔重复
+#[derive(Resource, Default)]
+pub struct GamepadMove {
+    //这一帧要走的方向
+    pub input: Option<MoveInput>,
+    repeat: DirectionRepeat,
+}
+
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GamepadMove>();
+
+        app.add_systems(PreUpdate, gamepad_move.after(InputSystem));
+
+        app.add_systems(
+            PreUpdate,
+            gamepad_egui_navigation
+                .after(gamepad_move)
+                .after(EguiSet::ProcessInput)
+                .before(EguiSet::BeginFrame)
+                .run_if(
+                    in_state(AppState::Menu)
+                        .or_else(in_state(AppState::KeyBindings))
+                        .or_else(in_state(GameState::Tab)),
+                ),
+        );
+    }
+}
+
+fn axis_direction(value: f32) -> i32 {
+    if value > GAMEPAD_STICK_THRESHOLD {
+        1
+    } else if value < -GAMEPAD_STICK_THRESHOLD {
+        -1
+    } else {
+        0
+    }
+}
+
+//所有手柄上按住的方向
+fn held_direction(
+    gamepads: &Gamepads,
+    buttons: &ButtonInput<GamepadButton>,
+    axes: &Axis<GamepadAxis>,
+) -> Option<(i32, i32)> {
+    let (mut x, mut y) = (0, 0);
+
+    for gamepad in gamepads.iter() {
+        let pressed = |button_type| buttons.pressed(GamepadButton::new(gamepad, button_type));
+
+        if pressed(GamepadButtonType::DPadUp) {
+            y += 1;
+        }
+
+        if pressed(GamepadButtonType::DPadDown) {
+            y -= 1;
+        }
+
+        if pressed(GamepadButtonType::DPadLeft) {
+            x -= 1;
+        }
+
+        if pressed(GamepadButtonType::DPadRight) {
+            x += 1;
+        }
+
+        let stick_x = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX));
+        let stick_y = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY));
+
+        x += axis_direction(stick_x.unwrap_or(0.0));
+        y += axis_direction(stick_y.unwrap_or(0.0));
+    }
+
+    let direction = (x.clamp(-1, 1), y.clamp(-1, 1));
+
+    (direction != (0, 0)).then_some(direction)
+}
+
+fn gamepad_move(
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    time: Res<Time>,
+    mut gamepad_move: ResMut<GamepadMove>,
+) {
+    let held = held_direction(&gamepads, &buttons, &axes);
+
+    gamepad_move.input = gamepad_move.repeat.update(held, time.delta());
+}
+
+fn push_key(input: &mut egui::RawInput, key: egui::Key) {
+    for pressed in [true, false] {
+        input.events.push(egui::Event::Key {
+            key,
+            physical_key: None,
+            pressed,
+            repeat: false,
+            modifiers: egui::Modifiers::NONE,
+        });
+    }
+}
+
+//把手柄输入转换成egui的按键，方向移动焦点，确认键点击有焦点的按钮
+fn gamepad_egui_navigation(
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_move: Res<GamepadMove>,
+    mut contexts: EguiContexts,
+    mut q_egui_input: Query<&mut EguiInput, With<PrimaryWindow>>,
+) {
+    let Ok(mut egui_input) = q_egui_input.get_single_mut() else {
+        return;
+    };
+
+    if let Some(MoveInput {
+        direction: (x, y), ..
+    }) = gamepad_move.input
+    {
+        //还没有焦点时先选中第一个控件
+        let has_focus = contexts
+            .ctx_mut()
+            .memory(|memory| memory.focused().is_some());
+
+        let key = if !has_focus {
+            egui::Key::Tab
+        } else if y > 0 {
+            egui::Key::ArrowUp
+        } else if y < 0 {
+            egui::Key::ArrowDown
+        } else if x < 0 {
+            egui::Key::ArrowLeft
+        } else {
+            egui::Key::ArrowRight
+        };
+
+        push_key(&mut egui_input.0, key);
+    }
+
+    let confirm = gamepads.iter().any(|gamepad| {
+        Action::Confirm
+            .gamepad_buttons()
+            .iter()
+            .any(|button_type| buttons.just_pressed(GamepadButton::new(gamepad, *button_type)))
+    });
+
+    if confirm {
+        push_key(&mut egui_input.0, egui::Key::Enter);
+    }
+}
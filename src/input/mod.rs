@@ -0,0 +1,359 @@
+mod gamepad;
+mod rebind;
+mod repeat;
+mod touch;
+
+use std::collections::BTreeMap;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+pub use gamepad::*;
+pub use rebind::*;
+pub use repeat::*;
+pub use touch::*;
+
+//按键绑定保存的位置
+pub const KEY_BINDINGS_PATH: &str = "key_bindings.ron";
+
+//游戏里的操作，系统只读取操作，不直接读取按键
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveNorth,
+    MoveSouth,
+    MoveWest,
+    MoveEast,
+    MoveNorthWest,
+    MoveNorthEast,
+    MoveSouthWest,
+    MoveSouthEast,
+    Wait,
+    Shoot,
+    Rest,
+    Disarm,
+    Explore,
+    OpenInventory,
+    MessageLog,
+    Confirm,
+    Cancel,
+    ZoomIn,
+    ZoomOut,
+}
+
+impl Action {
+    pub const ALL: [Action; 19] = [
+        Action::MoveNorth,
+        Action::MoveSouth,
+        Action::MoveWest,
+        Action::MoveEast,
+        Action::MoveNorthWest,
+        Action::MoveNorthEast,
+        Action::MoveSouthWest,
+        Action::MoveSouthEast,
+        Action::Wait,
+        Action::Shoot,
+        Action::Rest,
+        Action::Disarm,
+        Action::Explore,
+        Action::OpenInventory,
+        Action::MessageLog,
+        Action::Confirm,
+        Action::Cancel,
+        Action::ZoomIn,
+        Action::ZoomOut,
+    ];
+
+    //移动操作对应的方向，y轴向上，等待是(0, 0)
+    pub fn direction(&self) -> Option<(i32, i32)> {
+        match self {
+            Action::MoveNorth => Some((0, 1)),
+            Action::MoveSouth => Some((0, -1)),
+            Action::MoveWest => Some((-1, 0)),
+            Action::MoveEast => Some((1, 0)),
+            Action::MoveNorthWest => Some((-1, 1)),
+            Action::MoveNorthEast => Some((1, 1)),
+            Action::MoveSouthWest => Some((-1, -1)),
+            Action::MoveSouthEast => Some((1, -1)),
+            Action::Wait => Some((0, 0)),
+            _ => None,
+        }
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        match self {
+            Action::MoveNorth => vec![
+                KeyCode::KeyW,
+                KeyCode::ArrowUp,
+                KeyCode::Numpad8,
+                KeyCode::KeyK,
+            ],
+            Action::MoveSouth => vec![
+                KeyCode::KeyS,
+                KeyCode::ArrowDown,
+                KeyCode::Numpad2,
+                KeyCode::KeyJ,
+            ],
+            Action::MoveWest => vec![
+                KeyCode::KeyA,
+                KeyCode::ArrowLeft,
+                KeyCode::Numpad4,
+                KeyCode::KeyH,
+            ],
+            Action::MoveEast => vec![
+                KeyCode::KeyD,
+                KeyCode::ArrowRight,
+                KeyCode::Numpad6,
+                KeyCode::KeyL,
+            ],
+            Action::MoveNorthWest => vec![KeyCode::Numpad7, KeyCode::KeyY],
+            Action::MoveNorthEast => vec![KeyCode::Numpad9, KeyCode::KeyU],
+            Action::MoveSouthWest => vec![KeyCode::Numpad1, KeyCode::KeyB],
+            Action::MoveSouthEast => vec![KeyCode::Numpad3, KeyCode::KeyN],
+            Action::Wait => vec![KeyCode::Numpad5, KeyCode::Period, KeyCode::NumpadDecimal],
+            Action::Shoot => vec![KeyCode::KeyF],
+            Action::Rest => vec![KeyCode::KeyR],
+            Action::Disarm => vec![KeyCode::KeyX],
+            Action::Explore => vec![KeyCode::KeyO],
+            Action::OpenInventory => vec![KeyCode::Tab],
+            Action::MessageLog => vec![KeyCode::KeyM],
+            Action::Confirm => vec![KeyCode::Space, KeyCode::Enter],
+            Action::Cancel => vec![KeyCode::Escape],
+            Action::ZoomIn => vec![KeyCode::Equal, KeyCode::NumpadAdd],
+            Action::ZoomOut => vec![KeyCode::Minus, KeyCode::NumpadSubtract],
+        }
+    }
+}
+
+impl ToString for Action {
+    fn to_string(&self) -> String {
+        match *self {
+            Action::MoveNorth => format!("Move North"),
+            Action::MoveSouth => format!("Move South"),
+            Action::MoveWest => format!("Move West"),
+            Action::MoveEast => format!("Move East"),
+            Action::MoveNorthWest => format!("Move North-West"),
+            Action::MoveNorthEast => format!("Move North-East"),
+            Action::MoveSouthWest => format!("Move South-West"),
+            Action::MoveSouthEast => format!("Move South-East"),
+            Action::Wait => format!("Wait"),
+            Action::Shoot => format!("Shoot"),
+            Action::Rest => format!("Rest"),
+            Action::Disarm => format!("Disarm Trap"),
+            Action::Explore => format!("Auto Explore"),
+            Action::OpenInventory => format!("Inventory"),
+            Action::MessageLog => format!("Message Log"),
+            Action::Confirm => format!("Confirm"),
+            Action::Cancel => format!("Cancel"),
+            Action::ZoomIn => format!("Zoom In"),
+            Action::ZoomOut => format!("Zoom Out"),
+        }
+    }
+}
+
+//每个操作绑定的按键
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct KeyBindings(pub BTreeMap<Action, Vec<KeyCode>>);
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings(
+            Action::ALL
+                .iter()
+                .map(|action| (*action, action.default_keys()))
+                .collect(),
+        )
+    }
+}
+
+impl KeyBindings {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.0
+            .get(&action)
+            .map(|keys| keys.as_slice())
+            .unwrap_or(&[])
+    }
+
+    //一个按键只绑定一个操作，先从其他操作上解绑
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        for keys in self.0.values_mut() {
+            keys.retain(|bound| *bound != key);
+        }
+
+        self.0.entry(action).or_default().push(key);
+    }
+
+    pub fn clear(&mut self, action: Action) {
+        self.0.insert(action, vec![]);
+    }
+
+    //文件里没有的操作使用默认按键，但跳过已经绑定到其他操作上的按键
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    fn merge_saved(saved: KeyBindings) -> Self {
+        let mut bindings = saved;
+
+        for action in Action::ALL.iter() {
+            if bindings.0.contains_key(action) {
+                continue;
+            }
+
+            let keys = action
+                .default_keys()
+                .into_iter()
+                .filter(|key| !bindings.0.values().any(|keys| keys.contains(key)))
+                .collect();
+
+            bindings.0.insert(*action, keys);
+        }
+
+        bindings
+    }
+
+    //读取保存的绑定
+    pub fn load() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        match std::fs::read_to_string(KEY_BINDINGS_PATH) {
+            Ok(text) => match ron::from_str::<KeyBindings>(&text) {
+                Ok(saved) => return KeyBindings::merge_saved(saved),
+                Err(e) => warn!("failed to parse {}: {}", KEY_BINDINGS_PATH, e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("failed to read {}: {}", KEY_BINDINGS_PATH, e),
+        }
+
+        KeyBindings::default()
+    }
+
+    pub fn save(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let text = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+                Ok(text) => text,
+                Err(e) => {
+                    error!("failed to serialize key bindings: {}", e);
+                    return;
+                }
+            };
+
+            if let Err(e) = std::fs::write(KEY_BINDINGS_PATH, text) {
+                error!("failed to write {}: {}", KEY_BINDINGS_PATH, e);
+            }
+        }
+    }
+}
+
+//按照绑定把键盘和手柄的按键转换成操作
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    keyboard_input: Res<'w, ButtonInput<KeyCode>>,
+    key_bindings: Res<'w, KeyBindings>,
+    gamepad_input: Res<'w, ButtonInput<GamepadButton>>,
+    gamepad_move: Res<'w, GamepadMove>,
+    keyboard_move: Res<'w, KeyboardMove>,
+}
+
+impl<'w> ActionInput<'w> {
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.key_bindings
+            .keys(action)
+            .iter()
+            .any(|key| self.keyboard_input.just_pressed(*key))
+            || self
+                .gamepad_input
+                .get_just_pressed()
+                .any(|button| action.gamepad_buttons().contains(&button.button_type))
+    }
+
+    //这一帧按下了任意按键
+    pub fn any_just_pressed(&self) -> bool {
+        self.keyboard_input.get_just_pressed().next().is_some()
+            || self.gamepad_input.get_just_pressed().next().is_some()
+    }
+
+    //这一帧按下了没有绑定到这个操作的按键
+    pub fn any_other_just_pressed(&self, action: Action) -> bool {
+        let keys = self.key_bindings.keys(action);
+
+        self.keyboard_input
+            .get_just_pressed()
+            .any(|key| !keys.contains(key))
+            || self
+                .gamepad_input
+                .get_just_pressed()
+                .any(|button| !action.gamepad_buttons().contains(&button.button_type))
+    }
+
+    //这一帧的移动，同时按下多个方向时合成一个方向，按住不放时会重复，没有移动时返回None
+    pub fn move_direction(&self) -> Option<MoveInput> {
+        let mut pressed = false;
+        let (mut x, mut y) = (0, 0);
+
+        for action in Action::ALL.iter() {
+            let Some((dx, dy)) = action.direction() else {
+                continue;
+            };
+
+            if self.just_pressed(*action) {
+                pressed = true;
+                x += dx;
+                y += dy;
+            }
+        }
+
+        if pressed {
+            return Some(MoveInput {
+                direction: (x.clamp(-1, 1), y.clamp(-1, 1)),
+                repeated: false,
+            });
+        }
+
+        self.keyboard_move.input.or(self.gamepad_move.input)
+    }
+}
+
+pub struct InternalInputPlugin;
+
+impl Plugin for InternalInputPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(KeyBindings::load());
+
+        app.add_plugins((RebindPlugin, GamepadPlugin, TouchPlugin, KeyRepeatPlugin));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bind_moves_key_from_other_action() {
+        let mut bindings = KeyBindings::default();
+
+        bindings.bind(Action::Rest, KeyCode::KeyM);
+
+        assert!(bindings.keys(Action::Rest).contains(&KeyCode::KeyM));
+        assert!(bindings.keys(Action::Rest).contains(&KeyCode::KeyR));
+        assert!(!bindings.keys(Action::MessageLog).contains(&KeyCode::KeyM));
+    }
+
+    #[test]
+    fn merge_saved_fills_missing_actions_with_defaults() {
+        let mut saved = KeyBindings(BTreeMap::new());
+        saved.0.insert(Action::Rest, vec![KeyCode::KeyZ]);
+
+        let bindings = KeyBindings::merge_saved(saved);
+
+        assert_eq!(bindings.keys(Action::Rest), &[KeyCode::KeyZ]);
+        assert_eq!(bindings.keys(Action::MessageLog), &[KeyCode::KeyM]);
+    }
+
+    #[test]
+    fn merge_saved_skips_default_keys_used_by_saved_actions() {
+        let mut saved = KeyBindings(BTreeMap::new());
+        saved.0.insert(Action::Rest, vec![KeyCode::KeyM]);
+
+        let bindings = KeyBindings::merge_saved(saved);
+
+        assert_eq!(bindings.keys(Action::Rest), &[KeyCode::KeyM]);
+        assert!(bindings.keys(Action::MessageLog).is_empty());
+    }
+}
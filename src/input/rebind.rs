@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    egui::{self, Align2},
+    EguiContexts,
+};
+
+use super::{Action, ActionInput, KeyBindings};
+use crate::{state::AppStateManager, AppState};
+
+//正在等待按键的操作
+#[derive(Resource, Default)]
+pub struct RebindTarget(pub Option<Action>);
+
+pub struct RebindPlugin;
+
+impl Plugin for RebindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RebindTarget>();
+
+        app.add_systems(
+            Update,
+            (capture_key, rebind_ui)
+                .chain()
+                .run_if(in_state(AppState::KeyBindings)),
+        );
+
+        app.add_systems(OnExit(AppState::KeyBindings), clear_rebind_target);
+    }
+}
+
+fn clear_rebind_target(mut rebind_target: ResMut<RebindTarget>) {
+    rebind_target.0 = None;
+}
+
+fn keys_to_string(keys: &[KeyCode]) -> String {
+    keys.iter()
+        .map(|key| format!("{:?}", key))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+//等待按键时把下一个按键绑定到操作上，Esc取消等待，没有在等待时取消键回到菜单
+fn capture_key(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: ActionInput,
+    mut key_bindings: ResMut<KeyBindings>,
+    mut rebind_target: ResMut<RebindTarget>,
+    mut app_state_manager: AppStateManager,
+) {
+    let Some(action) = rebind_target.0 else {
+        if actions.just_pressed(Action::Cancel) {
+            app_state_manager.back_to_menu();
+        }
+
+        return;
+    };
+
+    let Some(key) = keyboard_input.get_just_pressed().next().copied() else {
+        return;
+    };
+
+    rebind_target.0 = None;
+
+    if key == KeyCode::Escape {
+        return;
+    }
+
+    key_bindings.bind(action, key);
+    key_bindings.save();
+}
+
+fn rebind_ui(
+    mut contexts: EguiContexts,
+    mut key_bindings: ResMut<KeyBindings>,
+    mut rebind_target: ResMut<RebindTarget>,
+    mut app_state_manager: AppStateManager,
+) {
+    egui::Window::new("Key Bindings")
+        .anchor(Align2::CENTER_CENTER, [0.0, 0.0])
+        .collapsible(false)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::Grid::new("key_bindings")
+                .striped(true)
+                .show(ui, |ui| {
+                    for action in Action::ALL.iter() {
+                        ui.label(action.to_string());
+
+                        if rebind_target.0 == Some(*action) {
+                            ui.label("Press a key, Esc to cancel");
+                        } else {
+                            ui.label(keys_to_string(key_bindings.keys(*action)));
+                        }
+
+                        if ui.button("Add").clicked() {
+                            rebind_target.0 = Some(*action);
+                        }
+
+                        if ui.button("Clear").clicked() {
+                            key_bindings.clear(*action);
+                            key_bindings.save();
+                        }
+
+                        ui.end_row();
+                    }
+                });
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button("Reset to Defaults").clicked() {
+                    *key_bindings = KeyBindings::default();
+                    key_bindings.save();
+                }
+
+                if ui.button("Back").clicked() {
+                    app_state_manager.back_to_menu();
+                }
+            });
+        });
+}
@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use bevy::{input::InputSystem, prelude::*};
+
+use super::{Action, KeyBindings};
+
+//按住方向后第一次重复前的等待时间
+pub const MOVE_REPEAT_DELAY: f32 = 0.3;
+
+//之后每次重复的间隔
+pub const MOVE_REPEAT_INTERVAL: f32 = 0.15;
+
+//一次移动输入，y轴向上，等待是(0, 0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveInput {
+    pub direction: (i32, i32),
+    //按住不放重复产生的移动
+    pub repeated: bool,
+}
+
+//按住方向时先等待一会，再按固定间隔重复
+#[derive(Debug)]
+pub struct DirectionRepeat {
+    held: Option<(i32, i32)>,
+    timer: Timer,
+}
+
+impl Default for DirectionRepeat {
+    fn default() -> Self {
+        DirectionRepeat {
+            held: None,
+            timer: Timer::from_seconds(MOVE_REPEAT_DELAY, TimerMode::Once),
+        }
+    }
+}
+
+impl DirectionRepeat {
+    //换了方向时立即返回新的方向，方向不变时等计时结束再重复
+    pub fn update(&mut self, held: Option<(i32, i32)>, delta: Duration) -> Option<MoveInput> {
+        if held != self.held {
+            self.held = held;
+            self.timer = Timer::from_seconds(MOVE_REPEAT_DELAY, TimerMode::Once);
+
+            return held.map(|direction| MoveInput {
+                direction,
+                repeated: false,
+            });
+        }
+
+        let direction = held?;
+
+        self.timer.tick(delta);
+
+        if !self.timer.finished() {
+            return None;
+        }
+
+        self.timer = Timer::from_seconds(MOVE_REPEAT_INTERVAL, TimerMode::Once);
+
+        Some(MoveInput {
+            direction,
+            repeated: true,
+        })
+    }
+}
+
+//按住移动键时重复的移动，第一次按下由ActionInput处理
+#[derive(Resource, Default)]
+pub struct KeyboardMove {
+    pub input: Option<MoveInput>,
+    repeat: DirectionRepeat,
+}
+
+pub struct KeyRepeatPlugin;
+
+impl Plugin for KeyRepeatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KeyboardMove>();
+
+        app.add_systems(PreUpdate, keyboard_move.after(InputSystem));
+    }
+}
+
+//按住的所有移动键合成的方向
+fn held_direction(
+    keyboard_input: &ButtonInput<KeyCode>,
+    key_bindings: &KeyBindings,
+) -> Option<(i32, i32)> {
+    let mut pressed = false;
+    let (mut x, mut y) = (0, 0);
+
+    for action in Action::ALL.iter() {
+        let Some((dx, dy)) = action.direction() else {
+            continue;
+        };
+
+        if key_bindings
+            .keys(*action)
+            .iter()
+            .any(|key| keyboard_input.pressed(*key))
+        {
+            pressed = true;
+            x += dx;
+            y += dy;
+        }
+    }
+
+    pressed.then(|| (x.clamp(-1, 1), y.clamp(-1, 1)))
+}
+
+fn keyboard_move(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    time: Res<Time>,
+    mut keyboard_move: ResMut<KeyboardMove>,
+) {
+    let held = held_direction(&keyboard_input, &key_bindings);
+
+    keyboard_move.input = keyboard_move
+        .repeat
+        .update(held, time.delta())
+        .filter(|input| input.repeated);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RIGHT: Option<(i32, i32)> = Some((1, 0));
+    const UP: Option<(i32, i32)> = Some((0, 1));
+
+    fn secs(seconds: f32) -> Duration {
+        Duration::from_secs_f32(seconds)
+    }
+
+    fn step(direction: (i32, i32), repeated: bool) -> Option<MoveInput> {
+        Some(MoveInput {
+            direction,
+            repeated,
+        })
+    }
+
+    #[test]
+    fn first_press_moves_immediately() {
+        let mut repeat = DirectionRepeat::default();
+
+        assert_eq!(repeat.update(RIGHT, Duration::ZERO), step((1, 0), false));
+    }
+
+    #[test]
+    fn waits_for_delay_before_repeating() {
+        let mut repeat = DirectionRepeat::default();
+        let almost = secs(MOVE_REPEAT_DELAY) - Duration::from_millis(1);
+
+        repeat.update(RIGHT, Duration::ZERO);
+
+        assert_eq!(repeat.update(RIGHT, almost), None);
+        assert_eq!(
+            repeat.update(RIGHT, Duration::from_millis(1)),
+            step((1, 0), true)
+        );
+    }
+
+    #[test]
+    fn repeats_at_steady_interval() {
+        let mut repeat = DirectionRepeat::default();
+        let almost = secs(MOVE_REPEAT_INTERVAL) - Duration::from_millis(1);
+
+        repeat.update(RIGHT, Duration::ZERO);
+        repeat.update(RIGHT, secs(MOVE_REPEAT_DELAY));
+
+        for _ in 0..3 {
+            assert_eq!(repeat.update(RIGHT, almost), None);
+            assert_eq!(
+                repeat.update(RIGHT, Duration::from_millis(1)),
+                step((1, 0), true)
+            );
+        }
+    }
+
+    #[test]
+    fn direction_change_resets_delay() {
+        let mut repeat = DirectionRepeat::default();
+
+        repeat.update(RIGHT, Duration::ZERO);
+        repeat.update(RIGHT, secs(MOVE_REPEAT_DELAY));
+
+        assert_eq!(repeat.update(UP, Duration::ZERO), step((0, 1), false));
+        assert_eq!(repeat.update(UP, secs(MOVE_REPEAT_INTERVAL)), None);
+        assert_eq!(
+            repeat.update(
+                UP,
+                secs(MOVE_REPEAT_DELAY) - secs(MOVE_REPEAT_INTERVAL) - Duration::from_millis(1)
+            ),
+            None
+        );
+        assert_eq!(
+            repeat.update(UP, Duration::from_millis(1)),
+            step((0, 1), true)
+        );
+    }
+
+    #[test]
+    fn release_stops_repeating() {
+        let mut repeat = DirectionRepeat::default();
+
+        repeat.update(RIGHT, Duration::ZERO);
+
+        assert_eq!(repeat.update(None, secs(MOVE_REPEAT_DELAY)), None);
+        assert_eq!(repeat.update(None, secs(MOVE_REPEAT_DELAY)), None);
+        assert_eq!(repeat.update(RIGHT, Duration::ZERO), step((1, 0), false));
+    }
+}
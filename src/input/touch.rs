@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use bevy_egui::{
+    egui::{self, Align2},
+    EguiContexts,
+};
+use bracket_pathfinding::prelude::Point;
+
+use crate::{
+    common::Position,
+    player::{player_move, Player, PlayerMoveEvent},
+    state::AppStateManager,
+    travel::TravelEvent,
+    ui::{CursorGrid, LogWindow},
+    GameState,
+};
+
+//手指移动超过这个距离算滑动，否则算点击
+pub const TOUCH_SWIPE_DISTANCE: f32 = 40.0;
+
+//触摸按钮的大小
+pub const TOUCH_BUTTON_SIZE: f32 = 80.0;
+
+//sin(22.5°)，滑动方向偏离坐标轴超过这个值时算斜向
+const SWIPE_DIAGONAL: f32 = 0.383;
+
+//是否显示触摸操作，手机上默认打开，其他设备第一次触摸屏幕时打开
+#[derive(Resource)]
+pub struct TouchControls {
+    pub enabled: bool,
+}
+
+impl Default for TouchControls {
+    fn default() -> Self {
+        TouchControls {
+            enabled: cfg!(any(target_os = "android", target_os = "ios")),
+        }
+    }
+}
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchControls>();
+
+        app.add_systems(Update, detect_touch);
+
+        app.add_systems(
+            Update,
+            (touch_move,)
+                .before(player_move)
+                .run_if(in_state(GameState::Playing).and_then(touch_controls_enabled)),
+        );
+
+        app.add_systems(
+            Update,
+            (touch_buttons,).before(player_move).run_if(
+                in_state(GameState::Playing)
+                    .or_else(in_state(GameState::Tab))
+                    .and_then(touch_controls_enabled),
+            ),
+        );
+    }
+}
+
+fn touch_controls_enabled(touch_controls: Res<TouchControls>) -> bool {
+    touch_controls.enabled
+}
+
+fn detect_touch(touches: Res<Touches>, mut touch_controls: ResMut<TouchControls>) {
+    if !touch_controls.enabled && touches.any_just_pressed() {
+        touch_controls.enabled = true;
+    }
+}
+
+//屏幕坐标y轴向下，地图y轴向上
+fn swipe_direction(offset: Vec2) -> Point {
+    let direction = offset.normalize_or_zero();
+
+    let axis = |value: f32| {
+        if value > SWIPE_DIAGONAL {
+            1
+        } else if value < -SWIPE_DIAGONAL {
+            -1
+        } else {
+            0
+        }
+    };
+
+    Point::new(axis(direction.x), axis(-direction.y))
+}
+
+//滑动向一个方向走一步，点击相邻的格子走过去或者攻击，点击远处的格子沿路径走过去
+fn touch_move(
+    touches: Res<Touches>,
+    cursor_grid: CursorGrid,
+    q_player: Query<&Position, With<Player>>,
+    mut contexts: EguiContexts,
+    mut move_ew: EventWriter<PlayerMoveEvent>,
+    mut travel_ew: EventWriter<TravelEvent>,
+) {
+    let Ok(position) = q_player.get_single() else {
+        return;
+    };
+
+    for touch in touches.iter_just_released() {
+        let start = touch.start_position();
+
+        //从按钮或者窗口上开始的触摸交给egui
+        if contexts
+            .ctx_mut()
+            .layer_id_at(egui::pos2(start.x, start.y))
+            .is_some()
+        {
+            continue;
+        }
+
+        let offset = touch.position() - start;
+
+        if offset.length() >= TOUCH_SWIPE_DISTANCE {
+            move_ew.send(PlayerMoveEvent(swipe_direction(offset)));
+            continue;
+        }
+
+        let Some(target) = cursor_grid.grid_position(touch.position()) else {
+            continue;
+        };
+
+        let delta = Point::new(target.x - position.x, target.y - position.y);
+
+        if delta == Point::zero() {
+            continue;
+        }
+
+        if delta.x.abs() <= 1 && delta.y.abs() <= 1 {
+            move_ew.send(PlayerMoveEvent(delta));
+        } else {
+            travel_ew.send(TravelEvent(Point::new(target.x, target.y)));
+        }
+    }
+}
+
+//右下角的背包、等待和日志按钮
+fn touch_buttons(
+    mut contexts: EguiContexts,
+    game_state: Res<State<GameState>>,
+    mut app_state_manager: AppStateManager,
+    mut log_window: ResMut<LogWindow>,
+    mut move_ew: EventWriter<PlayerMoveEvent>,
+) {
+    let playing = *game_state.get() == GameState::Playing;
+    let size = egui::vec2(TOUCH_BUTTON_SIZE, TOUCH_BUTTON_SIZE);
+
+    egui::Area::new(egui::Id::new("touch_buttons"))
+        .anchor(Align2::RIGHT_BOTTOM, [-16.0, -16.0])
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                let inventory = if playing { "Inventory" } else { "Close" };
+
+                if ui
+                    .add(egui::Button::new(inventory).min_size(size))
+                    .clicked()
+                {
+                    if playing {
+                        app_state_manager.start_tab();
+                    } else {
+                        app_state_manager.start_playing();
+                    }
+                }
+
+                if playing && ui.add(egui::Button::new("Wait").min_size(size)).clicked() {
+                    move_ew.send(PlayerMoveEvent(Point::zero()));
+                }
+
+                if ui.add(egui::Button::new("Log").min_size(size)).clicked() {
+                    log_window.toggle();
+                }
+            });
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swipe_right() {
+        assert_eq!(swipe_direction(Vec2::new(80.0, 5.0)), Point::new(1, 0));
+    }
+
+    //屏幕上向上滑y是负的，对应地图上y加一
+    #[test]
+    fn swipe_up() {
+        assert_eq!(swipe_direction(Vec2::new(-5.0, -80.0)), Point::new(0, 1));
+    }
+
+    #[test]
+    fn swipe_diagonal() {
+        assert_eq!(swipe_direction(Vec2::new(-60.0, 50.0)), Point::new(-1, -1));
+    }
+
+    #[test]
+    fn no_swipe() {
+        assert_eq!(swipe_direction(Vec2::ZERO), Point::new(0, 0));
+    }
+}
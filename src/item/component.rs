@@ -48,3 +48,17 @@ pub struct Consumable {}
 pub struct ProvidesHealing {
     pub heal_amount: i32,
 }
+
+//食物
+#[derive(Component, Debug)]
+pub struct ProvidesFood {}
+
+//箭矢，补充远程武器的弹药
+#[derive(Component, Debug)]
+pub struct ProvidesAmmo {
+    pub amount: i32,
+}
+
+//魅惑，目标加入玩家阵营
+#[derive(Component, Debug)]
+pub struct InflictsCharm {}
@@ -3,9 +3,11 @@ mod component;
 use bevy::{prelude::*, utils::HashMap};
 
 use crate::{
-    common::{CombatStats, GameLog, Position},
+    common::{CombatStats, GameLog, LogEntry, Position, RangedWeapon},
     core::TextureAssets,
     enemy::Enemy,
+    faction::Faction,
+    hunger::HungerClock,
     map::Map,
     AppState,
 };
@@ -88,7 +90,9 @@ fn item_use_healing(
     mut commands: Commands,
 ) {
     for (parent, wants_use_item, entity, item_target_entity) in q_wants_use_item.iter() {
-        let (consuable, healing) = q_items.get_mut(wants_use_item.item).unwrap();
+        let Ok((consuable, healing)) = q_items.get_mut(wants_use_item.item) else {
+            continue;
+        };
 
         for item_target in item_target_entity.0.iter() {
             if let Ok(mut stats) = q_stats.get_mut(*item_target) {
@@ -109,6 +113,126 @@ fn item_use_healing(
     }
 }
 
+//使用食物
+fn item_use_food(
+    q_wants_use_item: Query<(&Parent, &WantsToUseItem, Entity, &ItemTargetEntity)>,
+    mut q_hunger: Query<&mut HungerClock>,
+    q_items: Query<(Option<&Consumable>, &Name), (With<ProvidesFood>, With<InBackpack>)>,
+    mut item_remove_ew: EventWriter<ItemRemoveEvent>,
+    mut game_log: ResMut<GameLog>,
+    mut commands: Commands,
+) {
+    for (parent, wants_use_item, entity, item_target_entity) in q_wants_use_item.iter() {
+        let Ok((consuable, name)) = q_items.get(wants_use_item.item) else {
+            continue;
+        };
+
+        for item_target in item_target_entity.0.iter() {
+            if let Ok(mut hunger_clock) = q_hunger.get_mut(*item_target) {
+                hunger_clock.eat();
+
+                game_log.add(
+                    LogEntry::item()
+                        .text("You eat the ")
+                        .name(name)
+                        .text(". ")
+                        .good("You are no longer hungry."),
+                );
+            }
+        }
+
+        if let Some(_) = consuable {
+            commands.entity(entity).despawn_recursive();
+
+            item_remove_ew.send(ItemRemoveEvent {
+                owner: parent.get(),
+                item: wants_use_item.item,
+            });
+        }
+    }
+}
+
+//使用箭矢，补充目标远程武器的弹药
+fn item_use_ammo(
+    q_wants_use_item: Query<(&Parent, &WantsToUseItem, Entity, &ItemTargetEntity)>,
+    mut q_weapon: Query<&mut RangedWeapon>,
+    q_items: Query<(Option<&Consumable>, &ProvidesAmmo, &Name), With<InBackpack>>,
+    mut item_remove_ew: EventWriter<ItemRemoveEvent>,
+    mut game_log: ResMut<GameLog>,
+    mut commands: Commands,
+) {
+    for (parent, wants_use_item, entity, item_target_entity) in q_wants_use_item.iter() {
+        let Ok((consuable, ammo, name)) = q_items.get(wants_use_item.item) else {
+            continue;
+        };
+
+        for item_target in item_target_entity.0.iter() {
+            if let Ok(mut weapon) = q_weapon.get_mut(*item_target) {
+                weapon.ammo += ammo.amount;
+
+                game_log.add(
+                    LogEntry::item()
+                        .text("You gather the ")
+                        .name(name)
+                        .text(", ")
+                        .number(ammo.amount)
+                        .text(" more arrows."),
+                );
+            }
+        }
+
+        if let Some(_) = consuable {
+            commands.entity(entity).despawn_recursive();
+
+            item_remove_ew.send(ItemRemoveEvent {
+                owner: parent.get(),
+                item: wants_use_item.item,
+            });
+        }
+    }
+}
+
+//使用魅惑卷轴
+fn item_use_charm(
+    q_wants_use_item: Query<(&Parent, &WantsToUseItem, Entity, &ItemTargetEntity)>,
+    mut q_faction: Query<(&mut Faction, &Name)>,
+    q_items: Query<Option<&Consumable>, (With<InflictsCharm>, With<InBackpack>)>,
+    mut item_remove_ew: EventWriter<ItemRemoveEvent>,
+    mut game_log: ResMut<GameLog>,
+    mut commands: Commands,
+) {
+    for (parent, wants_use_item, entity, item_target_entity) in q_wants_use_item.iter() {
+        let Ok(consuable) = q_items.get(wants_use_item.item) else {
+            continue;
+        };
+
+        for item_target in item_target_entity.0.iter() {
+            if let Ok((mut faction, name)) = q_faction.get_mut(*item_target) {
+                if *faction == Faction::Player {
+                    continue;
+                }
+
+                *faction = Faction::Player;
+
+                game_log.add(
+                    LogEntry::item()
+                        .name(name)
+                        .good(" is charmed and joins you."),
+                );
+            }
+        }
+
+        if let Some(_) = consuable {
+            commands.entity(entity).despawn_recursive();
+
+            item_remove_ew.send(ItemRemoveEvent {
+                owner: parent.get(),
+                item: wants_use_item.item,
+            });
+        }
+    }
+}
+
 #[derive(Component, Debug)]
 pub struct InflictsDamage {
     pub damage: i32,
@@ -140,6 +264,9 @@ impl Plugin for ItemPlugin {
                 handle_item_update_event,
                 handle_item_apply_event,
                 item_use_healing,
+                item_use_food,
+                item_use_ammo,
+                item_use_charm,
                 compute_item_apply_position_or_entity,
             )
                 .run_if(in_state(AppState::InGame)),
@@ -268,7 +395,12 @@ pub fn item_collect(
                 .remove::<Position>()
                 .set_parent(wants_to_pickup_item.collected_by);
 
-            game_log.entries.push(format!("You pick up the {}.", name));
+            game_log.add(
+                LogEntry::item()
+                    .text("You pick up the ")
+                    .name(name)
+                    .text("."),
+            );
 
             item_ew.send(ItemPickUpEvent {
                 owner: parent.get(),
@@ -302,6 +434,9 @@ pub struct Item;
 pub enum ItemType {
     HealthPotion,
     MagicMissileScroll,
+    Rations,
+    CharmScroll,
+    Arrows,
 }
 
 impl ItemType {
@@ -309,6 +444,9 @@ impl ItemType {
         match self {
             ItemType::HealthPotion => texture_assets.i.clone(),
             ItemType::MagicMissileScroll => texture_assets.i.clone(),
+            ItemType::Rations => texture_assets.i.clone(),
+            ItemType::CharmScroll => texture_assets.i.clone(),
+            ItemType::Arrows => texture_assets.i.clone(),
         }
     }
 }
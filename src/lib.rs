@@ -1,20 +1,27 @@
 #![allow(clippy::type_complexity)]
 
 mod audio;
+mod camera;
 mod common;
 mod consts;
 mod core;
 mod enemy;
+mod faction;
+pub mod flow;
+mod hunger;
+mod input;
 mod item;
 mod loading;
 mod logic;
-mod map;
+pub mod map;
 mod menu;
 mod player;
 mod render;
 mod spawner;
 mod state;
 mod theme;
+mod trap;
+mod travel;
 mod ui;
 
 #[cfg(feature = "dev")]
@@ -23,9 +30,14 @@ mod dev;
 pub use state::{AppState, GameState};
 
 use crate::audio::InternalAudioPlugin;
+use crate::camera::CameraPlugin;
 use crate::common::CommonPlugin;
 use crate::core::InternalCorePlugin;
 use crate::enemy::EnemyPlugin;
+use crate::faction::FactionPlugin;
+use crate::flow::FlowMapPlugin;
+use crate::hunger::HungerPlugin;
+use crate::input::InternalInputPlugin;
 use crate::item::ItemPlugin;
 use crate::loading::LoadingPlugin;
 use crate::logic::LogicPlugin;
@@ -34,6 +46,8 @@ use crate::menu::MenuPlugin;
 use crate::player::PlayerPlugin;
 use crate::state::StatePlugin;
 use crate::theme::ThemePlugin;
+use crate::trap::TrapPlugin;
+use crate::travel::TravelPlugin;
 use crate::ui::InternalUiPlugin;
 use seldom_state::StateMachinePlugin;
 
@@ -62,6 +76,16 @@ impl Plugin for GamePlugin {
             InternalCorePlugin,
         ));
 
+        app.add_plugins((
+            HungerPlugin,
+            FactionPlugin,
+            FlowMapPlugin,
+            TrapPlugin,
+            CameraPlugin,
+            TravelPlugin,
+            InternalInputPlugin,
+        ));
+
         #[cfg(feature = "dev")]
         {
             use dev::DevPlugin;
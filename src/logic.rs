@@ -1,12 +1,15 @@
 use crate::{
-    common::{GameLog, RandomNumberGenerator},
-    map::{new_map_rooms_and_corridors, MapEntity},
+    common::{GameLog, GameTurn, RandomNumberGenerator},
+    map::{random_map, MapEntity},
     player::{PlayerEntity, PlayerPosition},
-    spawner::{self, spawn_room, ThemeContext},
+    spawner::{self, region_enemy_slots, spawn_prefab, spawn_region, ThemeContext},
     AppState,
 };
 use bevy::prelude::*;
-use bracket_pathfinding::prelude::Point;
+
+//每个区域最多生成的怪物和物品
+const MAX_REGION_ENEMY: usize = 4;
+const MAX_REGION_ITEM: usize = 4;
 
 pub struct LogicPlugin;
 
@@ -25,6 +28,7 @@ pub fn clear_game(mut commands: Commands, map_entity: Res<MapEntity>) {
     commands.remove_resource::<PlayerPosition>();
     commands.remove_resource::<MapEntity>();
     commands.remove_resource::<GameLog>();
+    commands.remove_resource::<GameTurn>();
 }
 
 fn setup_game(
@@ -32,7 +36,7 @@ fn setup_game(
     mut theme_context: ThemeContext,
     mut rng: ResMut<RandomNumberGenerator>,
 ) {
-    let map = new_map_rooms_and_corridors();
+    let map = random_map(&mut rng);
 
     let map_entity = map.spawn_tiles(
         &mut commands,
@@ -41,30 +45,47 @@ fn setup_game(
         &theme_context.theme,
     );
 
-    let first = map.rooms[0].center();
+    let start = map.start;
 
-    let player = spawner::player(&mut commands, &mut theme_context, first.0, first.1);
+    let player = spawner::player(&mut commands, &mut theme_context, start.x, start.y);
 
     commands.entity(player).set_parent(map_entity);
 
-    commands.insert_resource(PlayerPosition(Point::new(first.0, first.1)));
+    commands.insert_resource(PlayerPosition(start));
 
     commands.insert_resource(PlayerEntity(player));
 
-    for (i, room) in map.rooms.iter().skip(1).enumerate() {
-        spawn_room(
+    let regions = map.spawn_regions();
+
+    for (i, area) in regions.iter().enumerate() {
+        spawn_region(
             &mut commands,
             &mut theme_context,
             map_entity,
             &mut rng,
-            room,
+            area,
             i,
-            4,
-            4,
+            MAX_REGION_ENEMY,
+            MAX_REGION_ITEM,
         )
     }
 
+    spawn_prefab(
+        &mut commands,
+        &mut theme_context,
+        map_entity,
+        &map.spawn_list,
+        regions.len() * region_enemy_slots(MAX_REGION_ENEMY),
+    );
+
+    for point in map.door_positions() {
+        let door = spawner::door(&mut commands, &mut theme_context, point.x, point.y);
+
+        commands.entity(door).set_parent(map_entity);
+    }
+
     commands.insert_resource(MapEntity(map_entity));
     commands.insert_resource(map);
     commands.insert_resource(GameLog::default());
+    commands.insert_resource(GameTurn::default());
 }
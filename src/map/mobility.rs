@@ -0,0 +1,104 @@
+use bevy::ecs::query::QueryData;
+use bevy::prelude::*;
+use bevy::utils::smallvec::SmallVec;
+use bracket_pathfinding::prelude::{Algorithm2D, BaseMap, Point};
+
+use super::{CanOpenDoors, Map, Swimmer, TileType};
+
+//生物能走哪些格子，不会开门的把关着的门当成墙，会游泳的可以进入深水
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Mobility {
+    pub open_doors: bool,
+    pub swim: bool,
+}
+
+impl Default for Mobility {
+    //和Map本身的寻路一样，门可以通过，深水不能通过
+    fn default() -> Self {
+        Mobility {
+            open_doors: true,
+            swim: false,
+        }
+    }
+}
+
+//从CanOpenDoors和Swimmer读取生物的移动能力
+#[derive(QueryData)]
+pub struct MobilityQuery {
+    can_open_doors: Has<CanOpenDoors>,
+    swimmer: Has<Swimmer>,
+}
+
+impl<'w> MobilityQueryItem<'w> {
+    pub fn mobility(&self) -> Mobility {
+        Mobility {
+            open_doors: self.can_open_doors,
+            swim: self.swimmer,
+        }
+    }
+}
+
+impl Map {
+    //这个格子对有这种移动能力的生物来说能不能走
+    pub fn is_passable(&self, idx: usize, mobility: Mobility) -> bool {
+        let terrain = self.tiles[idx].is_walkable()
+            || (mobility.swim && self.tiles[idx] == TileType::DeepWater);
+
+        !self.blocked[idx] && terrain && (mobility.open_doors || self.doors[idx].is_none())
+    }
+
+    pub fn with_mobility(&self, mobility: Mobility) -> MobilityMap {
+        MobilityMap {
+            map: self,
+            mobility,
+        }
+    }
+}
+
+//按生物的移动能力寻路的地图
+pub struct MobilityMap<'a> {
+    pub map: &'a Map,
+    pub mobility: Mobility,
+}
+
+impl<'a> MobilityMap<'a> {
+    fn is_exit_valid(&self, x: i32, y: i32) -> bool {
+        if x < 1 || x > self.map.width - 1 || y < 1 || y > self.map.height - 1 {
+            return false;
+        }
+
+        self.map.is_passable(self.map.xy_idx(x, y), self.mobility)
+    }
+}
+
+impl<'a> Algorithm2D for MobilityMap<'a> {
+    fn dimensions(&self) -> Point {
+        self.map.dimensions()
+    }
+}
+
+impl<'a> BaseMap for MobilityMap<'a> {
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2)
+    }
+
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        let mut exits = SmallVec::new();
+        let point = self.map.index_to_point2d(idx);
+
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) == (0, 0) || !self.is_exit_valid(point.x + dx, point.y + dy) {
+                    continue;
+                }
+
+                let exit = self.map.xy_idx(point.x + dx, point.y + dy);
+                let base = if dx != 0 && dy != 0 { 1.45 } else { 1.0 };
+
+                exits.push((exit, base * self.map.tiles[exit].movement_cost()));
+            }
+        }
+
+        exits
+    }
+}
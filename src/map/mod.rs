@@ -0,0 +1,906 @@
+use bevy::ecs::system::Resource;
+use bevy::prelude::*;
+use bevy::utils::smallvec::SmallVec;
+use bracket_pathfinding::prelude::{line2d, Algorithm2D, BaseMap, DistanceAlg, LineAlg, Point};
+use bracket_random::prelude::RandomNumberGenerator;
+
+use crate::common::{CombatStats, GameLog, GameTurn, LogEntry, Position, SufferDamage};
+use crate::consts::{MAP_HEIGHT, MAP_WIDTH, MAP_Z_INDEX, SPRITE_SIZE};
+use crate::core::TextureAssets;
+use crate::enemy::Enemy;
+use crate::item::Item;
+use crate::player::Player;
+use crate::render::create_sprite_sheet_bundle;
+use crate::theme::Theme;
+use crate::{AppState, GameState};
+
+mod mobility;
+mod prefab;
+mod validate;
+mod wfc;
+
+pub use mobility::*;
+pub use prefab::*;
+pub use validate::*;
+pub use wfc::*;
+
+//站在熔岩上每回合受到的伤害
+pub const LAVA_DAMAGE: i32 = 4;
+
+//没有房间的地图按这个大小切成方块来生成怪物和物品
+pub const SPAWN_REGION_SIZE: i32 = 12;
+
+//出生点附近不生成东西
+pub const SPAWN_SAFE_DISTANCE: f32 = 8.0;
+
+#[derive(Resource, Deref)]
+pub struct MapEntity(pub Entity);
+
+#[derive(Component)]
+pub struct MapInstance;
+
+#[derive(Debug)]
+pub struct Rect {
+    pub x1: i32,
+    pub x2: i32,
+    pub y1: i32,
+    pub y2: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Rect {
+        Rect {
+            x1: x,
+            y1: y,
+            x2: x + w,
+            y2: y + h,
+        }
+    }
+
+    // Returns true if this overlaps with other
+    pub fn intersect(&self, other: &Rect) -> bool {
+        self.x1 <= other.x2 && self.x2 >= other.x1 && self.y1 <= other.y2 && self.y2 >= other.y1
+    }
+
+    pub fn center(&self) -> (i32, i32) {
+        ((self.x1 + self.x2) / 2, (self.y1 + self.y2) / 2)
+    }
+}
+
+pub struct MapPlugin;
+
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenDoorEvent>();
+
+        app.add_systems(Update, (map_index,).run_if(in_state(AppState::InGame)));
+        app.add_systems(Update, (open_doors,).run_if(in_state(GameState::Playing)));
+        app.add_systems(
+            Update,
+            (terrain_damage,).run_if(
+                in_state(GameState::Playing).and_then(resource_exists_and_changed::<GameTurn>),
+            ),
+        );
+    }
+}
+
+//站在熔岩之类的地形上每回合受到伤害
+fn terrain_damage(
+    mut commands: Commands,
+    mut q_creatures: Query<
+        (
+            Entity,
+            &Position,
+            &Name,
+            Has<Player>,
+            Option<&mut SufferDamage>,
+        ),
+        With<CombatStats>,
+    >,
+    map: Res<Map>,
+    mut log: ResMut<GameLog>,
+) {
+    for (entity, position, name, is_player, suffer_damage) in q_creatures.iter_mut() {
+        let idx = map.xy_idx(position.x, position.y);
+        let damage = map.tiles[idx].damage();
+
+        if damage <= 0 {
+            continue;
+        }
+
+        if is_player {
+            log.add(
+                LogEntry::combat()
+                    .text("The lava burns you, for ")
+                    .number(damage)
+                    .text(" hp."),
+            );
+        } else if map.visible_tiles[idx] {
+            log.add(
+                LogEntry::combat()
+                    .name(name)
+                    .text(" is burned by the lava, for ")
+                    .number(damage)
+                    .text(" hp."),
+            );
+        }
+
+        if let Some(mut suffer_damage) = suffer_damage {
+            suffer_damage.amount.push(damage);
+        } else {
+            commands.entity(entity).insert(SufferDamage {
+                amount: vec![damage],
+            });
+        }
+    }
+}
+
+//开门事件
+#[derive(Debug, Event)]
+pub struct OpenDoorEvent {
+    pub door: Entity,
+}
+
+pub fn open_doors(
+    mut commands: Commands,
+    mut open_door_er: EventReader<OpenDoorEvent>,
+    mut q_doors: Query<(&mut Door, &mut TextureAtlas, &mut Sprite)>,
+    theme: Res<Theme>,
+) {
+    for event in open_door_er.read() {
+        let Ok((mut door, mut atlas, mut sprite)) = q_doors.get_mut(event.door) else {
+            continue;
+        };
+
+        if door.open {
+            continue;
+        }
+
+        door.open = true;
+
+        let glyph = theme.door_to_render(true);
+        atlas.index = glyph.index;
+        sprite.color = glyph.color;
+
+        commands.entity(event.door).remove::<BlocksVisibility>();
+    }
+}
+
+pub fn map_index(
+    q_position: Query<Entity, With<Enemy>>,
+    q_blocks: Query<(&Position, Entity), With<BlocksTile>>,
+    q_items: Query<(&Position, Entity), With<Item>>,
+    q_blocks_visibility: Query<&Position, With<BlocksVisibility>>,
+    q_doors: Query<(&Position, Entity, &Door)>,
+    mut map: ResMut<Map>,
+) {
+    map.populate();
+    map.clear_content_index();
+
+    for (pos, entity) in q_blocks.iter() {
+        let idx = map.xy_idx(pos.x, pos.y);
+        map.blocked[idx] = true;
+
+        if let Ok(_) = q_position.get(entity) {
+            map.tile_content[idx].push(entity);
+        }
+    }
+
+    for (pos, entity) in q_items.iter() {
+        let idx = map.xy_idx(pos.x, pos.y);
+        map.items[idx] = Some(entity);
+    }
+
+    for pos in q_blocks_visibility.iter() {
+        let idx = map.xy_idx(pos.x, pos.y);
+        map.view_blocked[idx] = true;
+    }
+
+    for (pos, entity, door) in q_doors.iter() {
+        if door.open {
+            continue;
+        }
+
+        let idx = map.xy_idx(pos.x, pos.y);
+        map.doors[idx] = Some(entity);
+    }
+}
+
+#[derive(Component)]
+pub struct MapTile;
+
+#[derive(Component)]
+pub struct BlocksTile;
+
+//阻挡视线
+#[derive(Component)]
+pub struct BlocksVisibility;
+
+//门，关着的时候阻挡视线和移动，撞上去会打开
+#[derive(Component, Debug)]
+pub struct Door {
+    pub open: bool,
+}
+
+//可以开门的生物
+#[derive(Component, Debug)]
+pub struct CanOpenDoors;
+
+//会游泳的生物，可以进入深水
+#[derive(Component, Debug)]
+pub struct Swimmer;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum TileType {
+    Wall,
+    Floor,
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Grass,
+    Bridge,
+}
+
+impl TileType {
+    //不会游泳的生物能否走上去
+    pub fn is_walkable(&self) -> bool {
+        !matches!(self, TileType::Wall | TileType::DeepWater)
+    }
+
+    pub fn is_opaque(&self) -> bool {
+        matches!(self, TileType::Wall | TileType::Grass)
+    }
+
+    //寻路时走进这个格子的代价倍数，熔岩代价很高，怪物会尽量绕开
+    pub fn movement_cost(&self) -> f32 {
+        match self {
+            TileType::ShallowWater => 2.0,
+            TileType::Lava => 10.0,
+            _ => 1.0,
+        }
+    }
+
+    //站在上面每回合受到的伤害
+    pub fn damage(&self) -> i32 {
+        match self {
+            TileType::Lava => LAVA_DAMAGE,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Resource, Debug)]
+pub struct Map {
+    pub width: i32,
+    pub height: i32,
+    pub tiles: Vec<TileType>,
+    pub revealed_tiles: Vec<bool>,
+    pub rooms: Vec<Rect>,
+    pub visible_tiles: Vec<bool>,
+    pub blocked: Vec<bool>,
+    pub tile_content: Vec<Vec<Entity>>,
+    pub items: Vec<Option<Entity>>,
+    pub view_blocked: Vec<bool>,
+    //关着的门
+    pub doors: Vec<Option<Entity>>,
+    //玩家出生点
+    pub start: Point,
+    //预制房间里标出来需要生成的东西
+    pub spawn_list: Vec<(Point, PrefabSpawn)>,
+    //生成过程中每一步的地形，只在dev下记录，用来回放
+    pub history: Vec<Vec<TileType>>,
+}
+
+impl Algorithm2D for Map {
+    fn dimensions(&self) -> Point {
+        Point::new(self.width, self.height)
+    }
+}
+
+impl BaseMap for Map {
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.tiles[idx as usize].is_opaque() || self.view_blocked[idx]
+    }
+
+    //相邻格子之间按地形计算代价，远处的格子只用直线距离估算，代价不小于1所以估算不会偏大
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        let w = self.width as usize;
+        let p1 = Point::new(idx1 % w, idx1 / w);
+        let p2 = Point::new(idx2 % w, idx2 / w);
+        let distance = DistanceAlg::Pythagoras.distance2d(p1, p2);
+
+        if distance < 1.5 {
+            distance * self.tiles[idx2].movement_cost()
+        } else {
+            distance
+        }
+    }
+
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        let mut exits = SmallVec::new();
+        let x = idx as i32 % (self.width as i32);
+        let y = idx as i32 / (self.width as i32);
+        let w = self.width as usize;
+
+        //地形会让移动代价变高
+        let cost = |exit: usize, base: f32| base * self.tiles[exit].movement_cost();
+
+        // Cardinal directions
+        if self.is_exit_valid(x - 1, y) {
+            exits.push((idx - 1, cost(idx - 1, 1.0)))
+        };
+        if self.is_exit_valid(x + 1, y) {
+            exits.push((idx + 1, cost(idx + 1, 1.0)))
+        };
+        if self.is_exit_valid(x, y - 1) {
+            exits.push((idx - w, cost(idx - w, 1.0)))
+        };
+        if self.is_exit_valid(x, y + 1) {
+            exits.push((idx + w, cost(idx + w, 1.0)))
+        };
+
+        // Diagonals
+        if self.is_exit_valid(x - 1, y - 1) {
+            exits.push(((idx - w) - 1, cost((idx - w) - 1, 1.45)));
+        }
+        if self.is_exit_valid(x + 1, y - 1) {
+            exits.push(((idx - w) + 1, cost((idx - w) + 1, 1.45)));
+        }
+        if self.is_exit_valid(x - 1, y + 1) {
+            exits.push(((idx + w) - 1, cost((idx + w) - 1, 1.45)));
+        }
+        if self.is_exit_valid(x + 1, y + 1) {
+            exits.push(((idx + w) + 1, cost((idx + w) + 1, 1.45)));
+        }
+
+        exits
+    }
+}
+
+pub fn new_map_rooms_and_corridors(rng: &mut RandomNumberGenerator) -> Map {
+    let mut map = Map::default();
+
+    let mut rooms: Vec<Rect> = Vec::new();
+    const MAX_ROOMS: i32 = 30;
+    const MIN_SIZE: i32 = 6;
+    const MAX_SIZE: i32 = 10;
+
+    for _ in 0..MAX_ROOMS {
+        let w = rng.range(MIN_SIZE, MAX_SIZE);
+        let h = rng.range(MIN_SIZE, MAX_SIZE);
+        let x = rng.roll_dice(1, map.width - w - 1) - 1;
+        let y = rng.roll_dice(1, map.height - h - 1) - 1;
+        let new_room = Rect::new(x, y, w, h);
+        let mut ok = true;
+        for other_room in rooms.iter() {
+            if new_room.intersect(other_room) {
+                ok = false
+            }
+        }
+        if ok {
+            map.apply_room_to_map(&new_room);
+
+            if !rooms.is_empty() {
+                let (new_x, new_y) = new_room.center();
+                let (prev_x, prev_y) = rooms[rooms.len() - 1].center();
+                if rng.range(0, 2) == 1 {
+                    map.apply_horizontal_tunnel(prev_x, new_x, prev_y);
+                    map.apply_vertical_tunnel(prev_y, new_y, new_x);
+                } else {
+                    map.apply_vertical_tunnel(prev_y, new_y, prev_x);
+                    map.apply_horizontal_tunnel(prev_x, new_x, new_y);
+                }
+            }
+
+            rooms.push(new_room);
+
+            map.take_snapshot();
+        }
+    }
+
+    //部分房间里有草丛、水塘或者熔岩
+    for room in rooms.iter().skip(1) {
+        map.apply_room_feature(room, rng);
+    }
+
+    map.take_snapshot();
+
+    //偶尔在一个房间里放一座宝库
+    if rooms.len() > 1 && rng.roll_dice(1, 3) == 1 {
+        let room = &rooms[rng.range(1, rooms.len() as i32) as usize];
+        let vault = &VAULTS[rng.range(0, VAULTS.len() as i32) as usize];
+
+        if map.apply_vault(vault, room) {
+            map.take_snapshot();
+        }
+    }
+
+    if rng.roll_dice(1, 3) == 1 {
+        map.apply_river(&rooms, rng);
+        map.take_snapshot();
+    }
+
+    if let Some(first) = rooms.first() {
+        let (x, y) = first.center();
+        map.start = Point::new(x, y);
+    }
+
+    map.rooms = rooms;
+
+    map
+}
+
+//生成失败时最多重新生成几次
+pub const MAP_MAX_ATTEMPTS: usize = 5;
+
+//偶尔整层都是预制的地图，或者用波函数坍缩从房间和走廊的地图里学出来的地图
+//生成后检查能不能从出生点走到所有地方，走不到时先修复，修不好就重新生成
+//重新生成几次都不行时使用预制的地图，保证不会返回走不通的地图
+pub fn random_map(rng: &mut RandomNumberGenerator) -> Map {
+    for _ in 0..MAP_MAX_ATTEMPTS {
+        let mut map = build_map(rng);
+
+        if ensure_connected(&mut map) {
+            return map;
+        }
+
+        warn!("map repair failed, regenerating");
+    }
+
+    warn!(
+        "map generation failed {} times, using prefab map",
+        MAP_MAX_ATTEMPTS
+    );
+
+    new_map_from_prefab(&GOBLIN_CAMP)
+}
+
+//检查地图是否连通，不连通时修复，修不好时返回false
+fn ensure_connected(map: &mut Map) -> bool {
+    let report = map.connectivity();
+
+    if report.is_connected() {
+        return true;
+    }
+
+    warn!(
+        "map has {} disconnected regions, {} unreachable rooms, repairing",
+        report.regions.len(),
+        report.unreachable_rooms.len()
+    );
+
+    map.repair_connectivity()
+}
+
+fn build_map(rng: &mut RandomNumberGenerator) -> Map {
+    match rng.roll_dice(1, 10) {
+        1 => new_map_from_prefab(&GOBLIN_CAMP),
+        2 | 3 => {
+            let source = new_map_rooms_and_corridors(rng);
+
+            new_map_wfc(&source, rng)
+        }
+        _ => new_map_rooms_and_corridors(rng),
+    }
+}
+
+impl Map {
+    //获取地图上的可见的所有敌人实体
+    pub fn get_all_enemy(&self, start_position: &Position, x: i32, y: i32) -> Option<Vec<Entity>> {
+        let max_x = start_position.x + x;
+        let min_x = start_position.x - x;
+        let min_y = start_position.y - y;
+        let max_y = start_position.y + y;
+
+        let mut target = vec![];
+
+        for x in min_x..max_x {
+            for y in min_y..max_y {
+                let index = self.xy_idx(x, y);
+
+                if !self.visible_tiles[index] {
+                    continue;
+                }
+
+                if let Some(enemys) = self.tile_content.get(index) {
+                    for entity in enemys.iter() {
+                        target.push(*entity);
+                    }
+                }
+            }
+        }
+
+        if target.is_empty() {
+            return None;
+        } else {
+            return Some(target);
+        }
+    }
+
+    //视野内是否存在敌人
+    pub fn is_enemy_in_view(
+        &self,
+        visible_tiles: &[Point],
+        is_hostile: impl Fn(Entity) -> bool,
+    ) -> bool {
+        !self.enemies_in_view(visible_tiles, is_hostile).is_empty()
+    }
+
+    //视野内所有的敌人
+    pub fn enemies_in_view(
+        &self,
+        visible_tiles: &[Point],
+        is_hostile: impl Fn(Entity) -> bool,
+    ) -> Vec<Entity> {
+        visible_tiles
+            .iter()
+            .flat_map(|point| {
+                self.tile_content[self.xy_idx(point.x, point.y)]
+                    .iter()
+                    .copied()
+            })
+            .filter(|entity| is_hostile(*entity))
+            .collect()
+    }
+
+    //两点之间是否没有不透明的格子阻挡
+    pub fn has_line_of_fire(&self, start: Point, end: Point) -> bool {
+        line2d(LineAlg::Bresenham, start, end)
+            .into_iter()
+            .filter(|point| *point != start && *point != end)
+            .all(|point| self.in_bounds(point) && !self.is_opaque(self.point2d_to_index(point)))
+    }
+
+    //记录当前的地形
+    pub fn take_snapshot(&mut self) {
+        if cfg!(feature = "dev") {
+            self.history.push(self.tiles.clone());
+        }
+    }
+
+    //可以安全生成东西的格子，不在墙里、深水里或者熔岩上
+    pub fn is_safe_spawn(&self, idx: usize) -> bool {
+        let tile = self.tiles[idx];
+
+        tile.is_walkable() && tile.damage() == 0
+    }
+
+    //随机生成怪物和物品的区域
+    //有房间时每个房间一个区域，出生的房间除外；没有房间时把地图切成方块
+    pub fn spawn_regions(&self) -> Vec<Vec<Point>> {
+        if !self.rooms.is_empty() {
+            return self
+                .rooms
+                .iter()
+                .skip(1)
+                .map(|room| {
+                    let mut area = vec![];
+
+                    for y in room.y1 + 1..=room.y2 {
+                        for x in room.x1 + 1..=room.x2 {
+                            if self.is_safe_spawn(self.xy_idx(x, y)) {
+                                area.push(Point::new(x, y));
+                            }
+                        }
+                    }
+
+                    area
+                })
+                .collect();
+        }
+
+        let mut regions = vec![];
+
+        for region_y in (0..self.height).step_by(SPAWN_REGION_SIZE as usize) {
+            for region_x in (0..self.width).step_by(SPAWN_REGION_SIZE as usize) {
+                let mut area = vec![];
+
+                for y in region_y..(region_y + SPAWN_REGION_SIZE).min(self.height) {
+                    for x in region_x..(region_x + SPAWN_REGION_SIZE).min(self.width) {
+                        let point = Point::new(x, y);
+
+                        if self.is_safe_spawn(self.xy_idx(x, y))
+                            && DistanceAlg::Pythagoras.distance2d(point, self.start)
+                                > SPAWN_SAFE_DISTANCE
+                        {
+                            area.push(point);
+                        }
+                    }
+                }
+
+                if !area.is_empty() {
+                    regions.push(area);
+                }
+            }
+        }
+
+        regions
+    }
+
+    //走廊和房间相接的地方，两侧都是墙
+    pub fn door_positions(&self) -> Vec<Point> {
+        let mut positions: Vec<Point> = vec![];
+
+        let in_room = |x: i32, y: i32| {
+            self.rooms
+                .iter()
+                .any(|room| x > room.x1 && x <= room.x2 && y > room.y1 && y <= room.y2)
+        };
+
+        let is_wall = |x: i32, y: i32| {
+            x < 0
+                || x >= self.width
+                || y < 0
+                || y >= self.height
+                || self.tiles[self.xy_idx(x, y)] == TileType::Wall
+        };
+
+        for room in self.rooms.iter() {
+            let mut candidates = vec![];
+
+            for y in room.y1 + 1..=room.y2 {
+                candidates.push((room.x1, y, false));
+                candidates.push((room.x2 + 1, y, false));
+            }
+
+            for x in room.x1 + 1..=room.x2 {
+                candidates.push((x, room.y1, true));
+                candidates.push((x, room.y2 + 1, true));
+            }
+
+            for (x, y, horizontal_side) in candidates {
+                if x < 0 || x >= self.width || y < 0 || y >= self.height {
+                    continue;
+                }
+
+                if self.tiles[self.xy_idx(x, y)] != TileType::Floor || in_room(x, y) {
+                    continue;
+                }
+
+                let flanked = if horizontal_side {
+                    is_wall(x - 1, y) && is_wall(x + 1, y)
+                } else {
+                    is_wall(x, y - 1) && is_wall(x, y + 1)
+                };
+
+                let point = Point::new(x, y);
+
+                if flanked && !positions.contains(&point) {
+                    positions.push(point);
+                }
+            }
+        }
+
+        positions
+    }
+
+    pub fn clear_content_index(&mut self) {
+        for content in self.tile_content.iter_mut() {
+            content.clear();
+        }
+    }
+
+    pub fn populate(&mut self) {
+        for (i, tile) in self.tiles.iter_mut().enumerate() {
+            self.blocked[i] = *tile == TileType::Wall;
+            self.items[i] = None;
+            self.view_blocked[i] = false;
+            self.doors[i] = None;
+        }
+    }
+
+    fn is_exit_valid(&self, x: i32, y: i32) -> bool {
+        if x < 1 || x > self.width - 1 || y < 1 || y > self.height - 1 {
+            return false;
+        }
+        let idx = self.xy_idx(x, y);
+        self.is_walkable(idx)
+    }
+
+    //没有被阻挡，并且不会游泳也能走上去
+    pub fn is_walkable(&self, idx: usize) -> bool {
+        !self.blocked[idx] && self.tiles[idx].is_walkable()
+    }
+
+    fn apply_horizontal_tunnel(&mut self, x1: i32, x2: i32, y: i32) {
+        for x in x1.min(x2)..=x1.max(x2) {
+            let index = self.xy_idx(x, y);
+
+            if index > 0 && index < (self.width * self.height) as usize {
+                self.tiles[index] = TileType::Floor;
+            }
+        }
+    }
+
+    fn apply_vertical_tunnel(&mut self, y1: i32, y2: i32, x: i32) {
+        for y in y1.min(y2)..=y1.max(y2) {
+            let idx = self.xy_idx(x, y);
+            if idx > 0 && idx < (self.width * self.height) as usize {
+                self.tiles[idx] = TileType::Floor;
+            }
+        }
+    }
+
+    //地形只放在房间的一角，不会挡住穿过房间中心的走廊
+    fn apply_room_feature(&mut self, room: &Rect, rng: &mut RandomNumberGenerator) {
+        let feature = match rng.roll_dice(1, 6) {
+            1 => TileType::Grass,
+            2 => TileType::DeepWater,
+            3 => TileType::Lava,
+            _ => return,
+        };
+
+        let (cx, cy) = room.center();
+
+        let (x1, x2) = if rng.range(0, 2) == 0 {
+            (room.x1 + 1, cx - 1)
+        } else {
+            (cx + 1, room.x2)
+        };
+
+        let (y1, y2) = if rng.range(0, 2) == 0 {
+            (room.y1 + 1, cy - 1)
+        } else {
+            (cy + 1, room.y2)
+        };
+
+        for y in y1..=y2 {
+            for x in x1..=x2 {
+                let idx = self.xy_idx(x, y);
+
+                if self.tiles[idx] != TileType::Floor {
+                    continue;
+                }
+
+                //水塘边缘是浅水，可以绕过去
+                let edge = x == x1 || x == x2 || y == y1 || y == y2;
+
+                self.tiles[idx] = if feature == TileType::DeepWater && edge {
+                    TileType::ShallowWater
+                } else {
+                    feature
+                };
+            }
+        }
+    }
+
+    //从上到下流过地图的河，只在空地上流过，走廊和房间的中线上架桥
+    fn apply_river(&mut self, rooms: &[Rect], rng: &mut RandomNumberGenerator) {
+        let mut x = rng.range(self.width / 4, self.width * 3 / 4);
+
+        for y in 1..self.height - 1 {
+            x = (x + rng.range(-1, 2)).clamp(2, self.width - 3);
+
+            for (dx, tile) in [
+                (-1, TileType::ShallowWater),
+                (0, TileType::DeepWater),
+                (1, TileType::ShallowWater),
+            ] {
+                let idx = self.xy_idx(x + dx, y);
+
+                if self.tiles[idx] == TileType::Wall {
+                    continue;
+                }
+
+                let room = rooms.iter().find(|room| {
+                    x + dx > room.x1 && x + dx <= room.x2 && y > room.y1 && y <= room.y2
+                });
+
+                let bridge = match room {
+                    Some(room) => {
+                        let (cx, cy) = room.center();
+                        x + dx == cx || y == cy
+                    }
+                    None => true,
+                };
+
+                self.tiles[idx] = if tile == TileType::DeepWater && bridge {
+                    TileType::Bridge
+                } else {
+                    tile
+                };
+            }
+        }
+    }
+
+    fn apply_room_to_map(&mut self, room: &Rect) {
+        for y in room.y1 + 1..=room.y2 {
+            for x in room.x1 + 1..=room.x2 {
+                let index = self.xy_idx(x, y);
+                self.tiles[index] = TileType::Floor;
+            }
+        }
+    }
+
+    pub fn xy_idx(&self, x: i32, y: i32) -> usize {
+        (y as usize * self.width as usize) + x as usize
+    }
+
+    pub fn get_start_position(&self) -> Vec3 {
+        let x = self.width as f32 * SPRITE_SIZE[0] as f32 / -2.0;
+        let y = self.height as f32 * SPRITE_SIZE[1] as f32 / -2.0;
+
+        Vec3 {
+            x,
+            y,
+            z: MAP_Z_INDEX,
+        }
+    }
+
+    pub fn new(width: i32, height: i32) -> Map {
+        let width_u = width as usize;
+        let height_u = height as usize;
+
+        let tiles = vec![TileType::Wall; width_u * height_u];
+
+        let map = Map {
+            width,
+            height,
+            tiles,
+            revealed_tiles: vec![false; width_u * height_u],
+            rooms: vec![],
+            visible_tiles: vec![false; width_u * height_u],
+            blocked: vec![false; width_u * height_u],
+            tile_content: vec![vec![]; width_u * height_u],
+            items: vec![None; width_u * height_u],
+            view_blocked: vec![false; width_u * height_u],
+            doors: vec![None; width_u * height_u],
+            start: Point::zero(),
+            spawn_list: vec![],
+            history: vec![],
+        };
+
+        map
+    }
+
+    pub fn spawn_tiles(
+        &self,
+        commands: &mut Commands,
+        texture_assets: &TextureAssets,
+        layout_assets: &mut Assets<TextureAtlasLayout>,
+        theme: &Theme,
+    ) -> Entity {
+        let map_entity = commands
+            .spawn((
+                Name::new("Map"),
+                TransformBundle {
+                    local: Transform {
+                        translation: self.get_start_position(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                VisibilityBundle::default(),
+                MapInstance,
+            ))
+            .id();
+
+        let mut map_commands = commands.entity(map_entity);
+
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let index = self.xy_idx(x, y);
+
+                let tile = self.tiles[index];
+
+                let bundle = create_sprite_sheet_bundle(
+                    texture_assets,
+                    layout_assets,
+                    theme.tile_to_render(tile),
+                );
+
+                map_commands.with_children(|builder| {
+                    builder.spawn((bundle, Position { x, y }, MapTile));
+                });
+            }
+        }
+
+        map_entity
+    }
+}
+
+impl Default for Map {
+    fn default() -> Self {
+        Map::new(MAP_WIDTH, MAP_HEIGHT)
+    }
+}
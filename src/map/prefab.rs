@@ -0,0 +1,128 @@
+use bracket_pathfinding::prelude::{Algorithm2D, Point};
+
+use super::{Map, Rect, TileType};
+
+//预制房间里标出来需要生成的东西
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrefabSpawn {
+    Goblin,
+    HealthPotion,
+}
+
+//用文本画出来的预制房间
+//#是墙，.是地板，g是地精，!是药水，@是玩家出生点，其他字符保持地图原样
+pub struct Prefab {
+    pub template: &'static str,
+}
+
+//整层的地精营地
+pub const GOBLIN_CAMP: Prefab = Prefab {
+    template: include_str!("prefabs/goblin_camp.txt"),
+};
+
+//可以放进普通房间里的小型宝库，最外圈都是地板，不会挡住穿过房间的走廊
+pub const VAULTS: &[Prefab] = &[
+    Prefab {
+        template: include_str!("prefabs/treasure_vault.txt"),
+    },
+    Prefab {
+        template: include_str!("prefabs/goblin_den.txt"),
+    },
+];
+
+impl Prefab {
+    fn lines(&self) -> impl Iterator<Item = &'static str> {
+        self.template
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| !line.is_empty())
+    }
+
+    pub fn width(&self) -> i32 {
+        self.lines()
+            .map(|line| line.chars().count() as i32)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn height(&self) -> i32 {
+        self.lines().count() as i32
+    }
+}
+
+impl Map {
+    //把预制房间印到地图上，左下角在(x, y)，文本的第一行在最上面
+    pub fn apply_prefab(&mut self, prefab: &Prefab, x: i32, y: i32) {
+        let height = prefab.height();
+
+        for (dy, line) in prefab.lines().enumerate() {
+            for (dx, glyph) in line.chars().enumerate() {
+                let tx = x + dx as i32;
+                let ty = y + height - 1 - dy as i32;
+
+                if tx < 0 || tx >= self.width || ty < 0 || ty >= self.height {
+                    continue;
+                }
+
+                let idx = self.xy_idx(tx, ty);
+                let point = Point::new(tx, ty);
+
+                match glyph {
+                    '#' => self.tiles[idx] = TileType::Wall,
+                    '.' => self.tiles[idx] = TileType::Floor,
+                    'g' => {
+                        self.tiles[idx] = TileType::Floor;
+                        self.spawn_list.push((point, PrefabSpawn::Goblin));
+                    }
+                    '!' => {
+                        self.tiles[idx] = TileType::Floor;
+                        self.spawn_list.push((point, PrefabSpawn::HealthPotion));
+                    }
+                    '@' => {
+                        self.tiles[idx] = TileType::Floor;
+                        self.start = point;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    //把宝库放在房间正中间，房间放不下时返回false
+    pub fn apply_vault(&mut self, prefab: &Prefab, room: &Rect) -> bool {
+        let room_width = room.x2 - room.x1;
+        let room_height = room.y2 - room.y1;
+
+        if prefab.width() > room_width || prefab.height() > room_height {
+            return false;
+        }
+
+        let x = room.x1 + 1 + (room_width - prefab.width()) / 2;
+        let y = room.y1 + 1 + (room_height - prefab.height()) / 2;
+
+        self.apply_prefab(prefab, x, y);
+
+        true
+    }
+}
+
+//整层都是预制的地图，放在地图正中间
+pub fn new_map_from_prefab(prefab: &Prefab) -> Map {
+    let mut map = Map::default();
+
+    let x = ((map.width - prefab.width()) / 2).max(0);
+    let y = ((map.height - prefab.height()) / 2).max(0);
+
+    map.start = Point::new(-1, -1);
+    map.apply_prefab(prefab, x, y);
+    map.take_snapshot();
+
+    //模板里没有出生点时站在第一块地板上
+    if map.start == Point::new(-1, -1) {
+        if let Some(idx) = map.tiles.iter().position(|tile| *tile == TileType::Floor) {
+            map.start = map.index_to_point2d(idx);
+        }
+    }
+
+    map
+}
@@ -0,0 +1,160 @@
+use std::collections::VecDeque;
+
+use bracket_pathfinding::prelude::{Algorithm2D, DistanceAlg, Point};
+
+use super::{Map, TileType};
+
+//修复时最多挖几条走廊
+pub const MAX_REPAIR_TUNNELS: usize = 50;
+
+//从出生点出发的连通性检查结果
+#[derive(Debug, Default)]
+pub struct ConnectivityReport {
+    //能走到的格子
+    pub reachable: Vec<bool>,
+    //走不到的区域，每个区域是一组连在一起的格子
+    pub regions: Vec<Vec<usize>>,
+    //走不到的房间下标
+    pub unreachable_rooms: Vec<usize>,
+    //出生点在地图外或者站在不能走的格子上
+    pub invalid_start: bool,
+}
+
+impl ConnectivityReport {
+    pub fn is_connected(&self) -> bool {
+        !self.invalid_start && self.regions.is_empty()
+    }
+}
+
+impl Map {
+    //不管生物和门，只看地形能不能走
+    fn flood_fill(&self, start: usize, visited: &mut [bool]) -> Vec<usize> {
+        let mut region = vec![];
+        let mut open = VecDeque::new();
+
+        visited[start] = true;
+        open.push_back(start);
+
+        while let Some(idx) = open.pop_front() {
+            region.push(idx);
+
+            let point = self.index_to_point2d(idx);
+
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let next = Point::new(point.x + dx, point.y + dy);
+
+                    if !self.in_bounds(next) {
+                        continue;
+                    }
+
+                    let next = self.point2d_to_index(next);
+
+                    if visited[next] || !self.tiles[next].is_walkable() {
+                        continue;
+                    }
+
+                    visited[next] = true;
+                    open.push_back(next);
+                }
+            }
+        }
+
+        region
+    }
+
+    //从出生点开始填充，找出所有走不到的区域
+    pub fn connectivity(&self) -> ConnectivityReport {
+        let mut report = ConnectivityReport {
+            reachable: vec![false; self.tiles.len()],
+            ..Default::default()
+        };
+
+        if !self.in_bounds(self.start) {
+            report.invalid_start = true;
+            return report;
+        }
+
+        let start = self.point2d_to_index(self.start);
+
+        if !self.tiles[start].is_walkable() {
+            report.invalid_start = true;
+            return report;
+        }
+
+        let mut visited = vec![false; self.tiles.len()];
+
+        for idx in self.flood_fill(start, &mut visited) {
+            report.reachable[idx] = true;
+        }
+
+        for idx in 0..self.tiles.len() {
+            if visited[idx] || !self.tiles[idx].is_walkable() {
+                continue;
+            }
+
+            report.regions.push(self.flood_fill(idx, &mut visited));
+        }
+
+        report.unreachable_rooms = self
+            .rooms
+            .iter()
+            .enumerate()
+            .filter(|(_, room)| {
+                let (x, y) = room.center();
+                let idx = self.xy_idx(x, y);
+
+                self.tiles[idx].is_walkable() && !report.reachable[idx]
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        report
+    }
+
+    //从每个断开的区域挖一条走廊到最近的能走到的格子，修不好时返回false
+    pub fn repair_connectivity(&mut self) -> bool {
+        for _ in 0..MAX_REPAIR_TUNNELS {
+            let report = self.connectivity();
+
+            if report.is_connected() {
+                return true;
+            }
+
+            if report.invalid_start {
+                return false;
+            }
+
+            let from = self.index_to_point2d(report.regions[0][0]);
+
+            let Some(to) = (0..self.tiles.len())
+                .filter(|idx| report.reachable[*idx])
+                .map(|idx| self.index_to_point2d(idx))
+                .min_by(|a, b| {
+                    let a = DistanceAlg::Pythagoras.distance2d(from, *a);
+                    let b = DistanceAlg::Pythagoras.distance2d(from, *b);
+                    a.total_cmp(&b)
+                })
+            else {
+                return false;
+            };
+
+            self.apply_horizontal_tunnel(from.x, to.x, from.y);
+            self.apply_vertical_tunnel(from.y, to.y, to.x);
+            self.take_snapshot();
+        }
+
+        self.connectivity().is_connected()
+    }
+
+    //地图边缘必须是墙，不然生物会走出地图
+    pub fn has_closed_edges(&self) -> bool {
+        (0..self.width).all(|x| {
+            self.tiles[self.xy_idx(x, 0)] == TileType::Wall
+                && self.tiles[self.xy_idx(x, self.height - 1)] == TileType::Wall
+        }) && (0..self.height).all(|y| {
+            self.tiles[self.xy_idx(0, y)] == TileType::Wall
+                && self.tiles[self.xy_idx(self.width - 1, y)] == TileType::Wall
+        })
+    }
+}
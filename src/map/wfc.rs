@@ -0,0 +1,308 @@
+use bracket_pathfinding::prelude::{Algorithm2D, DijkstraMap, DistanceAlg, Point};
+use bracket_random::prelude::RandomNumberGenerator;
+
+use super::{new_map_rooms_and_corridors, Map, TileType};
+
+//从样本地图上切下来的图案边长
+pub const WFC_CHUNK_SIZE: i32 = 8;
+
+//出现矛盾时最多重来几次，之后退回普通的生成器
+pub const WFC_MAX_ATTEMPTS: usize = 10;
+
+//能走到的地板太少时也算失败
+pub const WFC_MIN_FLOOR: usize = 400;
+
+//相邻的四个方向，和exits、compatible_with的下标对应
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+fn opposite(direction: usize) -> usize {
+    direction ^ 1
+}
+
+#[derive(Debug, Clone)]
+struct MapChunk {
+    pattern: Vec<TileType>,
+    //每条边上哪些格子可以走
+    exits: [Vec<bool>; 4],
+    //每个方向上可以挨着放的图案
+    compatible_with: [Vec<usize>; 4],
+}
+
+//把样本地图切成不重叠的方块，去掉重复的图案
+fn build_patterns(map: &Map, chunk_size: i32) -> Vec<Vec<TileType>> {
+    let mut patterns: Vec<Vec<TileType>> = vec![];
+
+    for chunk_y in 0..map.height / chunk_size {
+        for chunk_x in 0..map.width / chunk_size {
+            let mut pattern = vec![];
+
+            for y in 0..chunk_size {
+                for x in 0..chunk_size {
+                    let idx = map.xy_idx(chunk_x * chunk_size + x, chunk_y * chunk_size + y);
+                    pattern.push(map.tiles[idx]);
+                }
+            }
+
+            if !patterns.contains(&pattern) {
+                patterns.push(pattern);
+            }
+        }
+    }
+
+    patterns
+}
+
+fn edge(pattern: &[TileType], chunk_size: i32, direction: usize) -> Vec<bool> {
+    (0..chunk_size)
+        .map(|i| {
+            let (x, y) = match direction {
+                0 => (i, 0),
+                1 => (i, chunk_size - 1),
+                2 => (0, i),
+                _ => (chunk_size - 1, i),
+            };
+
+            pattern[(y * chunk_size + x) as usize].is_walkable()
+        })
+        .collect()
+}
+
+//两条边都是墙，或者至少有一个出口对得上
+fn edges_match(a: &[bool], b: &[bool]) -> bool {
+    let a_open = a.iter().any(|exit| *exit);
+    let b_open = b.iter().any(|exit| *exit);
+
+    if !a_open && !b_open {
+        return true;
+    }
+
+    a.iter().zip(b.iter()).any(|(a, b)| *a && *b)
+}
+
+fn build_chunks(patterns: Vec<Vec<TileType>>, chunk_size: i32) -> Vec<MapChunk> {
+    let mut chunks: Vec<MapChunk> = patterns
+        .into_iter()
+        .map(|pattern| {
+            let exits = [0, 1, 2, 3].map(|direction| edge(&pattern, chunk_size, direction));
+
+            MapChunk {
+                pattern,
+                exits,
+                compatible_with: Default::default(),
+            }
+        })
+        .collect();
+
+    for a in 0..chunks.len() {
+        for direction in 0..DIRECTIONS.len() {
+            let compatible: Vec<usize> = (0..chunks.len())
+                .filter(|b| {
+                    edges_match(
+                        &chunks[a].exits[direction],
+                        &chunks[*b].exits[opposite(direction)],
+                    )
+                })
+                .collect();
+
+            chunks[a].compatible_with[direction] = compatible;
+        }
+    }
+
+    chunks
+}
+
+struct Solver<'a> {
+    chunks: &'a [MapChunk],
+    width: i32,
+    height: i32,
+    cells: Vec<Option<usize>>,
+}
+
+impl<'a> Solver<'a> {
+    fn new(chunks: &'a [MapChunk], width: i32, height: i32) -> Self {
+        Solver {
+            chunks,
+            width,
+            height,
+            cells: vec![None; (width * height) as usize],
+        }
+    }
+
+    fn neighbours(&self, x: i32, y: i32) -> impl Iterator<Item = (usize, usize)> + '_ {
+        DIRECTIONS
+            .iter()
+            .enumerate()
+            .filter_map(move |(direction, (dx, dy))| {
+                let (nx, ny) = (x + dx, y + dy);
+
+                if nx < 0 || nx >= self.width || ny < 0 || ny >= self.height {
+                    return None;
+                }
+
+                self.cells[(ny * self.width + nx) as usize].map(|chunk| (direction, chunk))
+            })
+    }
+
+    //和所有已经确定的邻居都能挨着放的图案
+    fn candidates(&self, x: i32, y: i32) -> Vec<usize> {
+        let mut candidates: Vec<usize> = (0..self.chunks.len()).collect();
+
+        for (direction, neighbour) in self.neighbours(x, y) {
+            let compatible = &self.chunks[neighbour].compatible_with[opposite(direction)];
+            candidates.retain(|chunk| compatible.contains(chunk));
+        }
+
+        candidates
+    }
+
+    //每次确定候选最少的格子，出现没有候选的格子时返回false
+    fn solve(&mut self, rng: &mut RandomNumberGenerator) -> bool {
+        let start = rng.range(0, self.cells.len() as i32) as usize;
+        self.cells[start] = Some(rng.range(0, self.chunks.len() as i32) as usize);
+
+        loop {
+            let mut best: Option<(usize, Vec<usize>)> = None;
+
+            for idx in 0..self.cells.len() {
+                if self.cells[idx].is_some() {
+                    continue;
+                }
+
+                let x = idx as i32 % self.width;
+                let y = idx as i32 / self.width;
+
+                //只从已经确定的区域向外扩展
+                if self.neighbours(x, y).next().is_none() {
+                    continue;
+                }
+
+                let candidates = self.candidates(x, y);
+
+                if candidates.is_empty() {
+                    return false;
+                }
+
+                if best
+                    .as_ref()
+                    .map_or(true, |(_, best)| candidates.len() < best.len())
+                {
+                    best = Some((idx, candidates));
+                }
+            }
+
+            let Some((idx, candidates)) = best else {
+                return true;
+            };
+
+            self.cells[idx] = Some(candidates[rng.range(0, candidates.len() as i32) as usize]);
+        }
+    }
+
+    fn render(&self, map: &mut Map, chunk_size: i32) {
+        for (idx, chunk) in self.cells.iter().enumerate() {
+            let Some(chunk) = chunk else {
+                continue;
+            };
+
+            let chunk_x = idx as i32 % self.width;
+            let chunk_y = idx as i32 / self.width;
+            let pattern = &self.chunks[*chunk].pattern;
+
+            for y in 0..chunk_size {
+                for x in 0..chunk_size {
+                    let map_idx = map.xy_idx(chunk_x * chunk_size + x, chunk_y * chunk_size + y);
+                    map.tiles[map_idx] = pattern[(y * chunk_size + x) as usize];
+                }
+            }
+        }
+    }
+}
+
+impl Map {
+    //四周围上墙，出生点放在离中心最近的地板上，把走不到的地方填成墙
+    //能走到的地板太少时返回false
+    fn finalize_wfc(&mut self) -> bool {
+        for x in 0..self.width {
+            let top = self.xy_idx(x, 0);
+            let bottom = self.xy_idx(x, self.height - 1);
+            self.tiles[top] = TileType::Wall;
+            self.tiles[bottom] = TileType::Wall;
+        }
+
+        for y in 0..self.height {
+            let left = self.xy_idx(0, y);
+            let right = self.xy_idx(self.width - 1, y);
+            self.tiles[left] = TileType::Wall;
+            self.tiles[right] = TileType::Wall;
+        }
+
+        let center = Point::new(self.width / 2, self.height / 2);
+
+        let Some(start) = (0..self.tiles.len())
+            .filter(|idx| self.tiles[*idx].is_walkable() && self.tiles[*idx].damage() == 0)
+            .min_by(|a, b| {
+                let a = DistanceAlg::Pythagoras.distance2d(self.index_to_point2d(*a), center);
+                let b = DistanceAlg::Pythagoras.distance2d(self.index_to_point2d(*b), center);
+                a.total_cmp(&b)
+            })
+        else {
+            return false;
+        };
+
+        self.start = self.index_to_point2d(start);
+
+        let max_depth = (self.width * self.height) as f32;
+        let dijkstra = DijkstraMap::new(self.width, self.height, &[start], &*self, max_depth);
+
+        let mut reachable = 0;
+
+        for (idx, distance) in dijkstra.map.iter().enumerate() {
+            if !self.tiles[idx].is_walkable() {
+                continue;
+            }
+
+            if *distance == f32::MAX {
+                self.tiles[idx] = TileType::Wall;
+            } else {
+                reachable += 1;
+            }
+        }
+
+        reachable >= WFC_MIN_FLOOR
+    }
+}
+
+//从样本地图学习局部图案，生成结构相似的新地图
+pub fn new_map_wfc(source: &Map, rng: &mut RandomNumberGenerator) -> Map {
+    let patterns = build_patterns(source, WFC_CHUNK_SIZE);
+    let chunks = build_chunks(patterns, WFC_CHUNK_SIZE);
+
+    if !chunks.is_empty() {
+        for _ in 0..WFC_MAX_ATTEMPTS {
+            let mut solver = Solver::new(
+                &chunks,
+                source.width / WFC_CHUNK_SIZE,
+                source.height / WFC_CHUNK_SIZE,
+            );
+
+            //出现矛盾时从头再来
+            if !solver.solve(rng) {
+                continue;
+            }
+
+            let mut map = Map::new(source.width, source.height);
+            solver.render(&mut map, WFC_CHUNK_SIZE);
+            map.take_snapshot();
+
+            let finished = map.finalize_wfc();
+            map.take_snapshot();
+
+            if finished {
+                return map;
+            }
+        }
+    }
+
+    //一直失败时退回普通的房间和走廊
+    new_map_rooms_and_corridors(rng)
+}
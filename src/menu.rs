@@ -9,9 +9,12 @@ pub struct MenuUiState {
 
 impl Default for MenuUiState {
     fn default() -> Self {
-        MenuUiState {
-            item_list: vec![MenuItem::playing()],
-        }
+        let mut item_list = vec![MenuItem::playing(), MenuItem::key_bindings()];
+
+        #[cfg(feature = "dev")]
+        item_list.push(MenuItem::map_gen());
+
+        MenuUiState { item_list }
     }
 }
 
@@ -26,6 +29,19 @@ impl MenuItem {
             item_type: MenuItemType::Playing,
         }
     }
+
+    pub fn key_bindings() -> Self {
+        MenuItem {
+            item_type: MenuItemType::KeyBindings,
+        }
+    }
+
+    #[cfg(feature = "dev")]
+    pub fn map_gen() -> Self {
+        MenuItem {
+            item_type: MenuItemType::MapGen,
+        }
+    }
 }
 
 #[derive(SystemParam)]
@@ -75,12 +91,18 @@ where
 #[derive(Debug, Clone)]
 pub enum MenuItemType {
     Playing,
+    KeyBindings,
+    #[cfg(feature = "dev")]
+    MapGen,
 }
 
 impl ToString for MenuItemType {
     fn to_string(&self) -> String {
         match *self {
             MenuItemType::Playing => format!("Playing"),
+            MenuItemType::KeyBindings => format!("Key Bindings"),
+            #[cfg(feature = "dev")]
+            MenuItemType::MapGen => format!("Map Generation"),
         }
     }
 }
@@ -107,6 +129,13 @@ fn handle_menu_item(
             MenuItemType::Playing => {
                 app_state_manager.start_game();
             }
+            MenuItemType::KeyBindings => {
+                app_state_manager.start_key_bindings();
+            }
+            #[cfg(feature = "dev")]
+            MenuItemType::MapGen => {
+                app_state_manager.start_map_gen();
+            }
         }
     }
 }
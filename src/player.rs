@@ -1,10 +1,17 @@
 use bevy::prelude::*;
-use bracket_pathfinding::prelude::Point;
+use bracket_pathfinding::prelude::{DistanceAlg, Point};
 
 use crate::{
-    common::{CombatStats, Position, WantsToMelee},
+    common::{
+        CombatStats, GameLog, GameTurn, LogEntry, Position, RangedWeapon, Viewshed, WantsToMelee,
+        WantsToShoot,
+    },
+    enemy::Enemy,
+    faction::{Faction, FactionTable, PlayerHostility, Reaction},
+    hunger::{HungerClock, HungerState},
+    input::{Action, ActionInput},
     item::WantsToPickupItem,
-    map::Map,
+    map::{Map, OpenDoorEvent, Swimmer, TileType},
     GameState,
 };
 
@@ -17,54 +24,304 @@ pub struct PlayerEntity(pub Entity);
 #[derive(Component)]
 pub struct Player;
 
+//每隔多少回合自然恢复一次生命
+pub const REGEN_INTERVAL: u64 = 10;
+
+//休息时跳过回合的间隔
+pub const REST_TURN_SECONDS: f32 = 0.1;
+
+//玩家正在休息，每次计时结束跳过一回合
+#[derive(Debug, Component, Deref, DerefMut)]
+#[component(storage = "SparseSet")]
+pub struct Resting(Timer);
+
+//正在浅水里跋涉，离开当前格子需要多花一回合
+#[derive(Debug, Component)]
+#[component(storage = "SparseSet")]
+pub struct Wading;
+
+impl Default for Resting {
+    fn default() -> Self {
+        Resting(Timer::from_seconds(REST_TURN_SECONDS, TimerMode::Repeating))
+    }
+}
+
+//玩家想要向某个方向走一格，键盘和鼠标的输入都会转换成这个事件
+#[derive(Event)]
+pub struct PlayerMoveEvent(pub Point);
+
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (player_input,).run_if(in_state(GameState::Playing)));
+        app.add_event::<PlayerMoveEvent>();
+
+        app.add_systems(
+            Update,
+            (
+                (player_input, player_move).chain(),
+                player_shoot,
+                start_rest,
+                player_rest,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+
+        app.add_systems(
+            Update,
+            (health_regeneration,).run_if(
+                in_state(GameState::Playing).and_then(resource_exists_and_changed::<GameTurn>),
+            ),
+        );
     }
 }
 
-fn get_input(keyboard_input: &ButtonInput<KeyCode>) -> Vec2 {
-    let mut input = Vec2::ZERO;
+//向视野内最近的敌人射击
+fn player_shoot(
+    mut commands: Commands,
+    actions: ActionInput,
+    q_player: Query<(Entity, &Position, &RangedWeapon), With<Player>>,
+    q_enemy: Query<(Entity, &Position, &Faction), With<Enemy>>,
+    faction_table: Res<FactionTable>,
+    map: Res<Map>,
+    mut game_turn: ResMut<GameTurn>,
+    mut log: ResMut<GameLog>,
+) {
+    if !actions.just_pressed(Action::Shoot) {
+        return;
+    }
+
+    let Ok((player, position, weapon)) = q_player.get_single() else {
+        return;
+    };
+
+    if weapon.ammo <= 0 {
+        log.add(LogEntry::combat().text("You are out of ammunition."));
+        return;
+    }
+
+    let start = Point::new(position.x, position.y);
+
+    let target = q_enemy
+        .iter()
+        .filter(|(_, pos, faction)| {
+            map.visible_tiles[map.xy_idx(pos.x, pos.y)]
+                && faction_table.reaction(Faction::Player, **faction) == Reaction::Attack
+        })
+        .map(|(entity, pos, _)| {
+            let end = Point::new(pos.x, pos.y);
+            (entity, end, DistanceAlg::Pythagoras.distance2d(start, end))
+        })
+        .filter(|(_, end, distance)| {
+            *distance <= weapon.range as f32 && map.has_line_of_fire(start, *end)
+        })
+        .min_by(|a, b| a.2.total_cmp(&b.2))
+        .map(|(entity, _, _)| entity);
+
+    let Some(target) = target else {
+        log.add(LogEntry::combat().text("No target in range."));
+        return;
+    };
+
+    commands.entity(player).with_children(|parent| {
+        parent.spawn(WantsToShoot { target });
+    });
+
+    game_turn.next();
+}
+
+fn start_rest(
+    mut commands: Commands,
+    actions: ActionInput,
+    q_player: Query<(Entity, &Viewshed, &CombatStats), (With<Player>, Without<Resting>)>,
+    map: Res<Map>,
+    hostility: PlayerHostility,
+    mut log: ResMut<GameLog>,
+) {
+    if !actions.just_pressed(Action::Rest) {
+        return;
+    }
+
+    let Ok((entity, viewshed, stats)) = q_player.get_single() else {
+        return;
+    };
+
+    if stats.hp >= stats.max_hp {
+        log.add(LogEntry::system().text("You are already at full health."));
+        return;
+    }
+
+    if map.is_enemy_in_view(&viewshed.visible_tiles, |entity| {
+        hostility.is_hostile(entity)
+    }) {
+        log.add(LogEntry::system().text("You cannot rest while enemies are nearby."));
+        return;
+    }
+
+    log.add(LogEntry::system().text("You rest."));
+
+    commands.entity(entity).insert(Resting::default());
+}
+
+fn player_rest(
+    mut commands: Commands,
+    actions: ActionInput,
+    mut q_player: Query<(Entity, &mut Resting, &Viewshed, &CombatStats), With<Player>>,
+    map: Res<Map>,
+    hostility: PlayerHostility,
+    time: Res<Time>,
+    mut game_turn: ResMut<GameTurn>,
+    mut log: ResMut<GameLog>,
+) {
+    let Ok((entity, mut resting, viewshed, stats)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    //按下其他按键时停止休息
+    if actions.any_other_just_pressed(Action::Rest) {
+        commands.entity(entity).remove::<Resting>();
+        return;
+    }
+
+    if map.is_enemy_in_view(&viewshed.visible_tiles, |entity| {
+        hostility.is_hostile(entity)
+    }) {
+        log.add(LogEntry::system().text("You stop resting, an enemy comes into view."));
+        commands.entity(entity).remove::<Resting>();
+        return;
+    }
 
-    if keyboard_input.just_pressed(KeyCode::KeyW) {
-        input.y += 1.0;
+    if stats.hp >= stats.max_hp {
+        log.add(LogEntry::system().good("You feel rested."));
+        commands.entity(entity).remove::<Resting>();
+        return;
     }
 
-    if keyboard_input.just_pressed(KeyCode::KeyS) {
-        input.y -= 1.0;
+    resting.tick(time.delta());
+
+    if resting.just_finished() {
+        game_turn.next();
     }
+}
 
-    if keyboard_input.just_pressed(KeyCode::KeyD) {
-        input.x += 1.0;
+//视野内没有敌人时缓慢恢复生命，饥饿时不会恢复
+fn health_regeneration(
+    mut q_player: Query<(&mut CombatStats, &Viewshed, Option<&HungerClock>), With<Player>>,
+    map: Res<Map>,
+    hostility: PlayerHostility,
+    game_turn: Res<GameTurn>,
+) {
+    if game_turn.0 % REGEN_INTERVAL != 0 {
+        return;
     }
 
-    if keyboard_input.just_pressed(KeyCode::KeyA) {
-        input.x -= 1.0;
+    let Ok((mut stats, viewshed, hunger_clock)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    if stats.hp >= stats.max_hp
+        || map.is_enemy_in_view(&viewshed.visible_tiles, |entity| {
+            hostility.is_hostile(entity)
+        })
+    {
+        return;
+    }
+
+    if let Some(hunger_clock) = hunger_clock {
+        if hunger_clock.state == HungerState::Hungry || hunger_clock.state == HungerState::Starving
+        {
+            return;
+        }
     }
 
-    input
+    stats.hp = (stats.hp + 1).min(stats.max_hp);
 }
 
-pub fn player_input(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut q_player: Query<&mut Position, With<Player>>,
+//视野内的敌人和物品
+fn things_in_view(map: &Map, viewshed: &Viewshed, hostility: &PlayerHostility) -> Vec<Entity> {
+    let mut things = map.enemies_in_view(&viewshed.visible_tiles, |entity| {
+        hostility.is_hostile(entity)
+    });
+
+    things.extend(
+        viewshed
+            .visible_tiles
+            .iter()
+            .filter_map(|point| map.items[map.xy_idx(point.x, point.y)]),
+    );
+
+    things
+}
+
+//按住移动键时重复移动，视野里出现新的敌人或物品时停下，直到重新按下
+fn player_input(
+    actions: ActionInput,
+    q_player: Query<&Viewshed, With<Player>>,
+    map: Res<Map>,
+    hostility: PlayerHostility,
+    mut seen: Local<Vec<Entity>>,
+    mut interrupted: Local<bool>,
+    mut move_ew: EventWriter<PlayerMoveEvent>,
+    mut log: ResMut<GameLog>,
+) {
+    let Some(input) = actions.move_direction() else {
+        return;
+    };
+
+    let Ok(viewshed) = q_player.get_single() else {
+        return;
+    };
+
+    let things = things_in_view(&map, viewshed, &hostility);
+
+    if !input.repeated {
+        *seen = things;
+        *interrupted = false;
+    } else if *interrupted {
+        return;
+    } else if things.iter().any(|thing| !seen.contains(thing)) {
+        log.add(LogEntry::system().text("You stop, something comes into view."));
+        *interrupted = true;
+        return;
+    }
+
+    let (x, y) = input.direction;
+
+    move_ew.send(PlayerMoveEvent(Point::new(x, y)));
+}
+
+pub fn player_move(
+    mut move_er: EventReader<PlayerMoveEvent>,
+    mut q_player: Query<(&mut Position, Has<Swimmer>, Has<Wading>), With<Player>>,
     mut player_position: ResMut<PlayerPosition>,
     player_entity: Res<PlayerEntity>,
     map: Res<Map>,
     q_combat_stats: Query<&mut CombatStats>,
+    q_faction: Query<&Faction>,
+    faction_table: Res<FactionTable>,
+    mut game_turn: ResMut<GameTurn>,
+    mut open_door_ew: EventWriter<OpenDoorEvent>,
+    mut log: ResMut<GameLog>,
     mut commands: Commands,
 ) {
-    let mut pos = match q_player.get_single_mut() {
-        Ok(pos) => pos,
+    let (mut pos, swimmer, wading) = match q_player.get_single_mut() {
+        Ok(player) => player,
         Err(_) => return,
     };
 
-    let input = get_input(&keyboard_input);
+    //一帧只走一步
+    let Some(PlayerMoveEvent(delta)) = move_er.read().last() else {
+        return;
+    };
+
+    //原地等待一回合
+    if *delta == Point::zero() {
+        game_turn.next();
+        return;
+    }
 
-    let new_pos_x = pos.x + input.x as i32;
-    let new_pos_y = pos.y + input.y as i32;
+    let new_pos_x = pos.x + delta.x;
+    let new_pos_y = pos.y + delta.y;
 
     let index = map.xy_idx(new_pos_x, new_pos_y);
 
@@ -75,6 +332,13 @@ pub fn player_input(
                 error!("tile content index error,entity is :{:?}", potential_target);
             }
             Ok(_t) => {
+                //不攻击盟友
+                if let Ok(faction) = q_faction.get(*potential_target) {
+                    if faction_table.reaction(Faction::Player, *faction) != Reaction::Attack {
+                        return;
+                    }
+                }
+
                 // Attack it
                 info!("From Hell's Heart, I stab thee!");
 
@@ -84,14 +348,42 @@ pub fn player_input(
                     parent.spawn(WantsToMelee { target: entity });
                 });
 
+                game_turn.next();
+
                 return; // So we don't move after attacking
             }
         }
     }
 
+    //撞到关着的门会把门打开，花费一回合
+    if let Some(door) = map.doors[index] {
+        open_door_ew.send(OpenDoorEvent { door });
+        log.add(LogEntry::system().text("You open the door."));
+
+        game_turn.next();
+
+        return;
+    }
+
     if map.blocked[index] {
         return;
     }
+
+    if !map.tiles[index].is_walkable() && !swimmer {
+        return;
+    }
+
+    //浅水里第一次移动只是在水里挣扎
+    let current = map.xy_idx(pos.x, pos.y);
+
+    if map.tiles[current] == TileType::ShallowWater && !wading {
+        commands.entity(player_entity.0).insert(Wading);
+        game_turn.next();
+        return;
+    }
+
+    commands.entity(player_entity.0).remove::<Wading>();
+
     if let Some(item_entity) = map.items[index] {
         commands.entity(player_entity.0).with_children(|parent| {
             parent.spawn(WantsToPickupItem {
@@ -105,4 +397,6 @@ pub fn player_input(
     pos.y = new_pos_y;
 
     player_position.0 = Point::new(new_pos_x, new_pos_y);
+
+    game_turn.next();
 }
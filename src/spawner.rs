@@ -9,21 +9,34 @@ use bevy::{
     sprite::TextureAtlasLayout,
 };
 
+use bracket_pathfinding::prelude::Point;
+
 use crate::{
-    common::{CombatStats, Position, RandomNumberGenerator, Viewshed},
-    consts::{ENEMY_Z_INDEX, ITEM_Z_INDEX, PLAYER_Z_INDEX},
+    common::{CombatStats, Position, RandomNumberGenerator, RangedWeapon, Viewshed},
+    consts::{DOOR_Z_INDEX, ENEMY_Z_INDEX, ITEM_Z_INDEX, PLAYER_Z_INDEX, TRAP_Z_INDEX},
     core::TextureAssets,
-    enemy::{add_state_machine, Enemy, EnemyType},
+    enemy::{add_state_machine, Enemy, EnemyType, KeepDistance, PackLeader, PackMember},
+    faction::Faction,
+    hunger::HungerClock,
     item::{
-        Consumable, InflictsDamage, Item, ItemTargetComputedType, ItemTargetType, ItemType,
-        ProvidesHealing, Ranged,
+        Consumable, InflictsCharm, InflictsDamage, Item, ItemTargetComputedType, ItemTargetType,
+        ItemType, ProvidesAmmo, ProvidesFood, ProvidesHealing, Ranged,
     },
-    map::{BlocksTile, Rect},
+    map::{BlocksTile, BlocksVisibility, Door, PrefabSpawn},
     player::Player,
     render::create_sprite_sheet_bundle,
     theme::Theme,
+    trap::{Hidden, Trap, TrapType},
 };
 
+//地精战团最多的人数，包括首领
+pub const WAR_BAND_MAX_SIZE: usize = 5;
+
+//每个区域预留的怪物编号数量，要放得下最大的战团，不然名字会和下一个区域重复
+pub fn region_enemy_slots(max_enemy: usize) -> usize {
+    max_enemy.max(WAR_BAND_MAX_SIZE)
+}
+
 #[derive(SystemParam)]
 pub struct ThemeContext<'w> {
     pub texture_assets: ResMut<'w, TextureAssets>,
@@ -62,6 +75,34 @@ pub fn magic_missile_scroll(
         .id()
 }
 
+pub fn charm_scroll(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    x: i32,
+    y: i32,
+) -> Entity {
+    let mut sprite_bundle = create_sprite_sheet_bundle(
+        &theme_context.texture_assets,
+        &mut theme_context.layout_assets,
+        theme_context.theme.item_to_render(ItemType::CharmScroll),
+    );
+    sprite_bundle.transform.translation.z = ITEM_Z_INDEX;
+
+    commands
+        .spawn((
+            sprite_bundle,
+            Position { x, y },
+            Name::new("Charm Scroll"),
+            Item {},
+            Consumable {},
+            Ranged { range: 6 },
+            InflictsCharm {},
+            ItemType::CharmScroll,
+            ItemTargetType::Computed(ItemTargetComputedType::Entity),
+        ))
+        .id()
+}
+
 pub fn health_potion(
     commands: &mut Commands,
     theme_context: &mut ThemeContext,
@@ -89,29 +130,178 @@ pub fn health_potion(
         .id()
 }
 
-pub fn spawn_room(
+pub fn rations(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    x: i32,
+    y: i32,
+) -> Entity {
+    let mut sprite_bundle = create_sprite_sheet_bundle(
+        &theme_context.texture_assets,
+        &mut theme_context.layout_assets,
+        theme_context.theme.item_to_render(ItemType::Rations),
+    );
+    sprite_bundle.transform.translation.z = ITEM_Z_INDEX;
+
+    commands
+        .spawn((
+            sprite_bundle,
+            Position { x, y },
+            Name::new("Rations"),
+            Item {},
+            ProvidesFood {},
+            Consumable {},
+            ItemType::Rations,
+            ItemTargetType::Owner,
+        ))
+        .id()
+}
+
+pub fn arrows(commands: &mut Commands, theme_context: &mut ThemeContext, x: i32, y: i32) -> Entity {
+    let mut sprite_bundle = create_sprite_sheet_bundle(
+        &theme_context.texture_assets,
+        &mut theme_context.layout_assets,
+        theme_context.theme.item_to_render(ItemType::Arrows),
+    );
+    sprite_bundle.transform.translation.z = ITEM_Z_INDEX;
+
+    commands
+        .spawn((
+            sprite_bundle,
+            Position { x, y },
+            Name::new("Arrows"),
+            Item {},
+            ProvidesAmmo { amount: 6 },
+            Consumable {},
+            ItemType::Arrows,
+            ItemTargetType::Owner,
+        ))
+        .id()
+}
+
+pub fn door(commands: &mut Commands, theme_context: &mut ThemeContext, x: i32, y: i32) -> Entity {
+    let mut sprite_bundle = create_sprite_sheet_bundle(
+        &theme_context.texture_assets,
+        &mut theme_context.layout_assets,
+        theme_context.theme.door_to_render(false),
+    );
+    sprite_bundle.transform.translation.z = DOOR_Z_INDEX;
+
+    commands
+        .spawn((
+            sprite_bundle,
+            Position { x, y },
+            Name::new("Door"),
+            Door { open: false },
+            BlocksVisibility,
+        ))
+        .id()
+}
+
+pub fn trap(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    trap_type: TrapType,
+    x: i32,
+    y: i32,
+) -> Entity {
+    let mut sprite_bundle = create_sprite_sheet_bundle(
+        &theme_context.texture_assets,
+        &mut theme_context.layout_assets,
+        theme_context.theme.trap_to_render(trap_type),
+    );
+    sprite_bundle.transform.translation.z = TRAP_Z_INDEX;
+
+    commands
+        .spawn((
+            sprite_bundle,
+            Position { x, y },
+            Name::new(trap_type.to_string()),
+            Trap,
+            Hidden,
+            trap_type,
+        ))
+        .id()
+}
+
+pub fn random_trap(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    rng: &mut RandomNumberGenerator,
+    x: i32,
+    y: i32,
+) -> Entity {
+    let trap_type = match rng.roll_dice(1, 3) {
+        1 => TrapType::BearTrap,
+        2 => TrapType::Teleport,
+        _ => TrapType::PoisonDart,
+    };
+
+    trap(commands, theme_context, trap_type, x, y)
+}
+
+//生成预制房间里标出来的怪物和物品
+pub fn spawn_prefab(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    map_entity: Entity,
+    spawn_list: &[(Point, PrefabSpawn)],
+    start_index: usize,
+) {
+    for (i, (point, spawn)) in spawn_list.iter().enumerate() {
+        let entity = match spawn {
+            PrefabSpawn::Goblin => {
+                let name = format!("Goblin #{}", start_index + i);
+
+                goblin(commands, theme_context, &name, point.x, point.y)
+            }
+            PrefabSpawn::HealthPotion => health_potion(commands, theme_context, point.x, point.y),
+        };
+
+        commands.entity(entity).set_parent(map_entity);
+    }
+}
+
+//在一片区域里随机生成怪物、物品和陷阱，区域里只包含可以安全站立的格子
+pub fn spawn_region(
     commands: &mut Commands,
     theme_context: &mut ThemeContext,
     map_entity: Entity,
     rng: &mut RandomNumberGenerator,
-    room: &Rect,
-    room_index: usize,
+    area: &[Point],
+    region_index: usize,
     max_enemy: usize,
     max_item: usize,
 ) {
+    if area.is_empty() {
+        return;
+    }
+
     let mut monster_spawn_points: Vec<Position> = Vec::new();
     let mut item_spawn_points: Vec<Position> = Vec::new();
 
-    let num_monsters = rng.roll_dice(1, max_enemy as i32 + 2) - 3;
+    //有一定几率生成一支地精战团
+    let war_band = rng.roll_dice(1, 5) == 1;
+
+    let num_monsters = if war_band {
+        rng.roll_dice(1, WAR_BAND_MAX_SIZE as i32 - 2) + 2
+    } else {
+        rng.roll_dice(1, max_enemy as i32 + 2) - 3
+    };
     let num_items = rng.roll_dice(1, max_item as i32 + 2) - 3;
 
+    //区域太小时放不下那么多东西
+    let num_monsters = num_monsters.min(area.len() as i32);
+    let num_items = num_items.min(area.len() as i32);
+
     for _i in 0..num_monsters {
         let mut added = false;
         while !added {
-            let x = room.x1 + rng.roll_dice(1, i32::abs(room.x2 - room.x1));
-            let y = room.y1 + rng.roll_dice(1, i32::abs(room.y2 - room.y1));
-
-            let pos = Position { x, y };
+            let point = area[rng.range(0, area.len() as i32) as usize];
+            let pos = Position {
+                x: point.x,
+                y: point.y,
+            };
 
             if !monster_spawn_points.contains(&pos) {
                 monster_spawn_points.push(pos);
@@ -123,9 +313,11 @@ pub fn spawn_room(
     for _i in 0..num_items {
         let mut added = false;
         while !added {
-            let x = room.x1 + rng.roll_dice(1, i32::abs(room.x2 - room.x1));
-            let y = room.y1 + rng.roll_dice(1, i32::abs(room.y2 - room.y1));
-            let pos = Position { x, y };
+            let point = area[rng.range(0, area.len() as i32) as usize];
+            let pos = Position {
+                x: point.x,
+                y: point.y,
+            };
 
             if !item_spawn_points.contains(&pos) {
                 item_spawn_points.push(pos);
@@ -134,12 +326,24 @@ pub fn spawn_room(
         }
     }
 
-    for (room_enemy_index, pos) in monster_spawn_points.iter().enumerate() {
-        let enemy_index = room_index * max_enemy + room_enemy_index;
+    let start_index = region_index * region_enemy_slots(max_enemy);
+
+    if war_band {
+        goblin_war_band(
+            commands,
+            theme_context,
+            map_entity,
+            &monster_spawn_points,
+            start_index,
+        );
+    } else {
+        for (room_enemy_index, pos) in monster_spawn_points.iter().enumerate() {
+            let enemy_index = start_index + room_enemy_index;
 
-        let enemy = random_enemy(commands, theme_context, rng, pos.x, pos.y, enemy_index);
+            let enemy = random_enemy(commands, theme_context, rng, pos.x, pos.y, enemy_index);
 
-        commands.entity(enemy).set_parent(map_entity);
+            commands.entity(enemy).set_parent(map_entity);
+        }
     }
 
     for pos in item_spawn_points.iter() {
@@ -147,6 +351,21 @@ pub fn spawn_room(
 
         commands.entity(item_entity).set_parent(map_entity);
     }
+
+    //有一定几率在房间里放一个陷阱，不和怪物或物品放在一起
+    if rng.roll_dice(1, 4) == 1 {
+        let point = area[rng.range(0, area.len() as i32) as usize];
+        let pos = Position {
+            x: point.x,
+            y: point.y,
+        };
+
+        if !monster_spawn_points.contains(&pos) && !item_spawn_points.contains(&pos) {
+            let trap_entity = random_trap(commands, theme_context, rng, pos.x, pos.y);
+
+            commands.entity(trap_entity).set_parent(map_entity);
+        }
+    }
 }
 
 pub fn player(commands: &mut Commands, theme_context: &mut ThemeContext, x: i32, y: i32) -> Entity {
@@ -168,12 +387,19 @@ pub fn player(commands: &mut Commands, theme_context: &mut ThemeContext, x: i32,
                 dirty: true,
             },
             Name::new("Player"),
+            Faction::Player,
             CombatStats {
                 max_hp: 30,
                 hp: 30,
                 defense: 2,
                 power: 5,
             },
+            HungerClock::default(),
+            RangedWeapon {
+                range: 6,
+                ammo: 12,
+                power: 4,
+            },
         ))
         .id()
 }
@@ -205,6 +431,7 @@ pub fn enemy(
                 dirty: true,
             },
             Name::new(name.to_owned()),
+            enemy_tile.faction(),
             BlocksTile,
             CombatStats {
                 max_hp: 16,
@@ -244,6 +471,118 @@ pub fn orc(
     monster
 }
 
+pub fn goblin_chief(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    name: &str,
+    x: i32,
+    y: i32,
+) -> Entity {
+    let monster = enemy(commands, theme_context, EnemyType::G, name, x, y);
+
+    commands.entity(monster).insert((
+        CombatStats {
+            max_hp: 24,
+            hp: 24,
+            defense: 2,
+            power: 4,
+        },
+        PackLeader,
+    ));
+
+    monster
+}
+
+//第一个位置生成首领，其余位置生成跟随首领的地精
+pub fn goblin_war_band(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    map_entity: Entity,
+    spawn_points: &[Position],
+    start_index: usize,
+) {
+    let Some((leader_pos, member_points)) = spawn_points.split_first() else {
+        return;
+    };
+
+    let name = format!("Goblin Chief #{}", start_index);
+    let leader = goblin_chief(commands, theme_context, &name, leader_pos.x, leader_pos.y);
+
+    commands.entity(leader).set_parent(map_entity);
+
+    for (i, pos) in member_points.iter().enumerate() {
+        let name = format!("Goblin #{}", start_index + i + 1);
+        let member = goblin(commands, theme_context, &name, pos.x, pos.y);
+
+        commands
+            .entity(member)
+            .insert(PackMember { leader })
+            .set_parent(map_entity);
+    }
+}
+
+pub fn archer(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    name: &str,
+    x: i32,
+    y: i32,
+) -> Entity {
+    let monster = enemy(commands, theme_context, EnemyType::A, name, x, y);
+
+    commands.entity(monster).insert((
+        RangedWeapon {
+            range: 6,
+            ammo: 8,
+            power: 4,
+        },
+        KeepDistance(3.0),
+    ));
+
+    monster
+}
+
+pub fn spitter(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    name: &str,
+    x: i32,
+    y: i32,
+) -> Entity {
+    let monster = enemy(commands, theme_context, EnemyType::S, name, x, y);
+
+    commands.entity(monster).insert((
+        RangedWeapon {
+            range: 4,
+            ammo: 20,
+            power: 3,
+        },
+        KeepDistance(2.0),
+    ));
+
+    monster
+}
+
+//中立的动物，不会主动攻击，看到玩家和害虫会躲开
+pub fn frog(
+    commands: &mut Commands,
+    theme_context: &mut ThemeContext,
+    name: &str,
+    x: i32,
+    y: i32,
+) -> Entity {
+    let monster = enemy(commands, theme_context, EnemyType::F, name, x, y);
+
+    commands.entity(monster).insert(CombatStats {
+        max_hp: 8,
+        hp: 8,
+        defense: 0,
+        power: 2,
+    });
+
+    monster
+}
+
 pub fn random_item(
     commands: &mut Commands,
     theme_context: &mut ThemeContext,
@@ -253,7 +592,7 @@ pub fn random_item(
 ) -> Entity {
     let roll: i32;
     {
-        roll = rng.roll_dice(1, 2);
+        roll = rng.roll_dice(1, 5);
     }
 
     match roll {
@@ -261,6 +600,18 @@ pub fn random_item(
             return health_potion(commands, theme_context, x, y);
         }
 
+        2 => {
+            return rations(commands, theme_context, x, y);
+        }
+
+        3 => {
+            return charm_scroll(commands, theme_context, x, y);
+        }
+
+        4 => {
+            return arrows(commands, theme_context, x, y);
+        }
+
         __ => {
             return magic_missile_scroll(commands, theme_context, x, y);
         }
@@ -277,7 +628,7 @@ pub fn random_enemy(
 ) -> Entity {
     let roll: i32;
     {
-        roll = rng.roll_dice(1, 2);
+        roll = rng.roll_dice(1, 5);
     }
 
     match roll {
@@ -287,6 +638,24 @@ pub fn random_enemy(
             return goblin(commands, theme_context, &name, x, y);
         }
 
+        2 => {
+            let name = format!("Kobold Archer #{}", i);
+
+            return archer(commands, theme_context, &name, x, y);
+        }
+
+        3 => {
+            let name = format!("Spitter #{}", i);
+
+            return spitter(commands, theme_context, &name, x, y);
+        }
+
+        4 => {
+            let name = format!("Frog #{}", i);
+
+            return frog(commands, theme_context, &name, x, y);
+        }
+
         __ => {
             let name = format!("Orc #{}", i);
 
@@ -28,6 +28,19 @@ impl<'w> AppStateManager<'w> {
     pub fn start_tab(&mut self) {
         self.game_next_state.set(GameState::Tab);
     }
+
+    #[cfg(feature = "dev")]
+    pub fn start_map_gen(&mut self) {
+        self.app_next_state.set(AppState::MapGen);
+    }
+
+    pub fn start_key_bindings(&mut self) {
+        self.app_next_state.set(AppState::KeyBindings);
+    }
+
+    pub fn back_to_menu(&mut self) {
+        self.app_next_state.set(AppState::Menu);
+    }
 }
 
 pub struct StatePlugin;
@@ -48,6 +61,11 @@ pub enum AppState {
     InGame,
     // Here the menu is drawn and waiting for player interaction
     Menu,
+    //修改按键绑定
+    KeyBindings,
+    //回放地图生成的过程
+    #[cfg(feature = "dev")]
+    MapGen,
 }
 
 #[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
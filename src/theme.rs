@@ -1,4 +1,4 @@
-use crate::{enemy::EnemyType, item::ItemType, map::TileType, render::Glyph};
+use crate::{enemy::EnemyType, item::ItemType, map::TileType, render::Glyph, trap::TrapType};
 use bevy::prelude::*;
 
 pub struct ThemePlugin;
@@ -18,6 +18,10 @@ pub trait MapTheme: 'static + Sync + Send {
     fn player_to_render(&self) -> Glyph;
 
     fn enemy_to_render(&self, enemy_type: EnemyType) -> Glyph;
+
+    fn door_to_render(&self, open: bool) -> Glyph;
+
+    fn trap_to_render(&self, trap_type: TrapType) -> Glyph;
 }
 
 #[derive(Resource, Deref)]
@@ -42,6 +46,26 @@ impl MapTheme for DefaultTheme {
                 color: Color::rgba(0.0, 1.0, 0.0, 1.0),
                 index: '#' as usize,
             },
+            TileType::ShallowWater => Glyph {
+                color: Color::CYAN,
+                index: '~' as usize,
+            },
+            TileType::DeepWater => Glyph {
+                color: Color::BLUE,
+                index: 247,
+            },
+            TileType::Lava => Glyph {
+                color: Color::ORANGE_RED,
+                index: 247,
+            },
+            TileType::Grass => Glyph {
+                color: Color::DARK_GREEN,
+                index: '"' as usize,
+            },
+            TileType::Bridge => Glyph {
+                color: Color::rgba(0.545, 0.271, 0.075, 1.0),
+                index: '=' as usize,
+            },
         }
     }
 
@@ -56,6 +80,21 @@ impl MapTheme for DefaultTheme {
                 color: Color::PURPLE,
                 index: ')' as usize,
             },
+
+            ItemType::Rations => Glyph {
+                color: Color::GREEN,
+                index: '%' as usize,
+            },
+
+            ItemType::CharmScroll => Glyph {
+                color: Color::PINK,
+                index: ')' as usize,
+            },
+
+            ItemType::Arrows => Glyph {
+                color: Color::rgba(0.545, 0.271, 0.075, 1.0),
+                index: '/' as usize,
+            },
         }
     }
 
@@ -69,6 +108,22 @@ impl MapTheme for DefaultTheme {
                 color: Color::rgba(0.529, 0.529, 0.529, 1.0),
                 index: '#' as usize,
             },
+            TileType::ShallowWater => Glyph {
+                color: Color::rgba(0.529, 0.529, 0.529, 1.0),
+                index: '~' as usize,
+            },
+            TileType::DeepWater | TileType::Lava => Glyph {
+                color: Color::rgba(0.529, 0.529, 0.529, 1.0),
+                index: 247,
+            },
+            TileType::Grass => Glyph {
+                color: Color::rgba(0.529, 0.529, 0.529, 1.0),
+                index: '"' as usize,
+            },
+            TileType::Bridge => Glyph {
+                color: Color::rgba(0.529, 0.529, 0.529, 1.0),
+                index: '=' as usize,
+            },
         }
     }
 
@@ -89,6 +144,51 @@ impl MapTheme for DefaultTheme {
                 color: Color::RED,
                 index: 'O' as usize,
             },
+            EnemyType::A => Glyph {
+                color: Color::ORANGE,
+                index: 'a' as usize,
+            },
+            EnemyType::S => Glyph {
+                color: Color::LIME_GREEN,
+                index: 's' as usize,
+            },
+            EnemyType::F => Glyph {
+                color: Color::SEA_GREEN,
+                index: 'f' as usize,
+            },
+        }
+    }
+
+    fn door_to_render(&self, open: bool) -> Glyph {
+        let color = Color::rgba(0.545, 0.271, 0.075, 1.0);
+
+        if open {
+            Glyph {
+                color,
+                index: '/' as usize,
+            }
+        } else {
+            Glyph {
+                color,
+                index: '+' as usize,
+            }
+        }
+    }
+
+    fn trap_to_render(&self, trap_type: TrapType) -> Glyph {
+        match trap_type {
+            TrapType::BearTrap => Glyph {
+                color: Color::SILVER,
+                index: '^' as usize,
+            },
+            TrapType::Teleport => Glyph {
+                color: Color::FUCHSIA,
+                index: '^' as usize,
+            },
+            TrapType::PoisonDart => Glyph {
+                color: Color::LIME_GREEN,
+                index: '^' as usize,
+            },
         }
     }
 }
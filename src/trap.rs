@@ -0,0 +1,299 @@
+use bevy::{prelude::*, utils::HashMap};
+use bracket_pathfinding::prelude::Point;
+
+use crate::{
+    common::{
+        CombatStats, GameLog, GameTurn, LogEntry, Position, RandomNumberGenerator, SufferDamage,
+        Viewshed,
+    },
+    input::{Action, ActionInput},
+    map::Map,
+    player::{Player, PlayerPosition},
+    GameState,
+};
+
+//每回合发现视野内隐藏陷阱的几率，掷1d20不小于这个值时发现
+pub const PERCEPTION_DC: i32 = 15;
+
+//拆除陷阱掷1d20不小于这个值时成功
+pub const DISARM_DC: i32 = 8;
+
+pub const BEAR_TRAP_DAMAGE: i32 = 6;
+pub const POISON_DART_DAMAGE: i32 = 3;
+
+#[derive(Debug, Component, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub enum TrapType {
+    BearTrap,
+    Teleport,
+    PoisonDart,
+}
+
+impl ToString for TrapType {
+    fn to_string(&self) -> String {
+        match *self {
+            TrapType::BearTrap => format!("Bear Trap"),
+            TrapType::Teleport => format!("Teleport Trap"),
+            TrapType::PoisonDart => format!("Poison Dart Trap"),
+        }
+    }
+}
+
+impl TrapType {
+    //提示框里的说明
+    pub fn description(&self) -> String {
+        match *self {
+            TrapType::BearTrap => format!(
+                "Snaps shut on whoever steps here for {} hp. Breaks after use.",
+                BEAR_TRAP_DAMAGE
+            ),
+            TrapType::Teleport => format!("Sends whoever steps here somewhere else on the level."),
+            TrapType::PoisonDart => format!(
+                "Fires a poisoned dart at whoever steps here for {} hp.",
+                POISON_DART_DAMAGE
+            ),
+        }
+    }
+
+    //触发一次后就会坏掉
+    pub fn single_activation(&self) -> bool {
+        *self == TrapType::BearTrap
+    }
+}
+
+#[derive(Debug, Component)]
+pub struct Trap;
+
+//还没有被玩家发现
+#[derive(Debug, Component)]
+pub struct Hidden;
+
+pub struct TrapPlugin;
+
+impl Plugin for TrapPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<TrapType>();
+
+        app.add_systems(
+            Update,
+            (trigger_traps, disarm_trap).run_if(in_state(GameState::Playing)),
+        );
+
+        app.add_systems(
+            Update,
+            (spot_traps,).run_if(
+                in_state(GameState::Playing).and_then(resource_exists_and_changed::<GameTurn>),
+            ),
+        );
+    }
+}
+
+//视野内的隐藏陷阱每回合做一次察觉检定
+fn spot_traps(
+    mut commands: Commands,
+    q_player: Query<&Viewshed, With<Player>>,
+    q_traps: Query<(Entity, &Position, &TrapType), (With<Trap>, With<Hidden>)>,
+    mut rng: ResMut<RandomNumberGenerator>,
+    mut log: ResMut<GameLog>,
+) {
+    let Ok(viewshed) = q_player.get_single() else {
+        return;
+    };
+
+    for (entity, position, trap_type) in q_traps.iter() {
+        let point = Point::new(position.x, position.y);
+
+        if !viewshed.visible_tiles.contains(&point) {
+            continue;
+        }
+
+        if rng.roll_dice(1, 20) < PERCEPTION_DC {
+            continue;
+        }
+
+        commands.entity(entity).remove::<Hidden>();
+
+        log.add(
+            LogEntry::system()
+                .text("You spot a ")
+                .name(trap_type.to_string())
+                .text("."),
+        );
+    }
+}
+
+//任何生物走进陷阱所在的格子都会触发
+fn trigger_traps(
+    mut commands: Commands,
+    mut q_creatures: Query<
+        (
+            Entity,
+            &mut Position,
+            &Name,
+            Option<&mut Viewshed>,
+            Option<&mut SufferDamage>,
+            Has<Player>,
+        ),
+        (With<CombatStats>, Without<Trap>),
+    >,
+    q_traps: Query<(Entity, &Position, &TrapType), With<Trap>>,
+    mut last_positions: Local<HashMap<Entity, Position>>,
+    mut map: ResMut<Map>,
+    mut player_position: ResMut<PlayerPosition>,
+    mut rng: ResMut<RandomNumberGenerator>,
+    mut log: ResMut<GameLog>,
+) {
+    last_positions.retain(|entity, _| q_creatures.contains(*entity));
+
+    for (entity, mut position, name, viewshed, suffer_damage, is_player) in q_creatures.iter_mut() {
+        //只在生物刚走到一个新格子时检查
+        if last_positions.get(&entity) == Some(&*position) {
+            continue;
+        }
+
+        last_positions.insert(entity, *position);
+
+        let Some((trap, _, trap_type)) = q_traps.iter().find(|(_, pos, _)| **pos == *position)
+        else {
+            continue;
+        };
+
+        let idx = map.xy_idx(position.x, position.y);
+        let seen = is_player || map.visible_tiles[idx];
+
+        if seen {
+            log.add(
+                LogEntry::combat()
+                    .name(name)
+                    .text(" triggers a ")
+                    .name(trap_type.to_string())
+                    .text("!"),
+            );
+        }
+
+        match trap_type {
+            TrapType::BearTrap | TrapType::PoisonDart => {
+                let damage = if *trap_type == TrapType::BearTrap {
+                    BEAR_TRAP_DAMAGE
+                } else {
+                    POISON_DART_DAMAGE
+                };
+
+                if seen {
+                    log.add(
+                        LogEntry::combat()
+                            .name(name)
+                            .text(" suffers ")
+                            .number(damage)
+                            .text(" hp damage."),
+                    );
+                }
+
+                if let Some(mut suffer_damage) = suffer_damage {
+                    suffer_damage.amount.push(damage);
+                } else {
+                    commands.entity(entity).insert(SufferDamage {
+                        amount: vec![damage],
+                    });
+                }
+            }
+            TrapType::Teleport => {
+                if let Some(destination) = teleport_destination(&map, &mut rng) {
+                    map.blocked[idx] = false;
+                    map.blocked[map.xy_idx(destination.x, destination.y)] = true;
+
+                    *position = destination;
+                    last_positions.insert(entity, destination);
+
+                    if let Some(mut viewshed) = viewshed {
+                        viewshed.dirty = true;
+                    }
+
+                    if is_player {
+                        player_position.0 = Point::new(destination.x, destination.y);
+                    }
+
+                    if seen {
+                        log.add(LogEntry::combat().name(name).text(" vanishes!"));
+                    }
+                }
+            }
+        }
+
+        if trap_type.single_activation() {
+            commands.entity(trap).despawn_recursive();
+        } else {
+            commands.entity(trap).remove::<Hidden>();
+        }
+    }
+}
+
+//随机找一个房间里可以站的空格子
+fn teleport_destination(map: &Map, rng: &mut RandomNumberGenerator) -> Option<Position> {
+    if map.rooms.is_empty() {
+        return None;
+    }
+
+    for _ in 0..100 {
+        let room = &map.rooms[rng.range(0, map.rooms.len() as i32) as usize];
+
+        let x = room.x1 + rng.roll_dice(1, i32::abs(room.x2 - room.x1));
+        let y = room.y1 + rng.roll_dice(1, i32::abs(room.y2 - room.y1));
+        let idx = map.xy_idx(x, y);
+
+        if map.is_walkable(idx) && map.tiles[idx].damage() == 0 {
+            return Some(Position { x, y });
+        }
+    }
+
+    None
+}
+
+//拆除玩家身边已经发现的陷阱，花费一回合
+fn disarm_trap(
+    mut commands: Commands,
+    actions: ActionInput,
+    q_player: Query<&Position, With<Player>>,
+    q_traps: Query<(Entity, &Position, &TrapType), (With<Trap>, Without<Hidden>)>,
+    mut rng: ResMut<RandomNumberGenerator>,
+    mut game_turn: ResMut<GameTurn>,
+    mut log: ResMut<GameLog>,
+) {
+    if !actions.just_pressed(Action::Disarm) {
+        return;
+    }
+
+    let Ok(player_position) = q_player.get_single() else {
+        return;
+    };
+
+    let trap = q_traps.iter().find(|(_, position, _)| {
+        (position.x - player_position.x).abs() <= 1
+            && (position.y - player_position.y).abs() <= 1
+            && **position != *player_position
+    });
+
+    let Some((trap, _, trap_type)) = trap else {
+        log.add(LogEntry::system().text("There is no trap to disarm."));
+        return;
+    };
+
+    if rng.roll_dice(1, 20) >= DISARM_DC {
+        log.add(
+            LogEntry::system()
+                .good("You disarm the ")
+                .name(trap_type.to_string())
+                .good("."),
+        );
+        commands.entity(trap).despawn_recursive();
+    } else {
+        log.add(
+            LogEntry::system()
+                .text("You fail to disarm the ")
+                .name(trap_type.to_string())
+                .text("."),
+        );
+    }
+
+    game_turn.next();
+}
@@ -0,0 +1,360 @@
+use bevy::prelude::*;
+use bevy::utils::smallvec::SmallVec;
+use bracket_pathfinding::prelude::{a_star_search, Algorithm2D, BaseMap, DijkstraMap, Point};
+
+use crate::{
+    common::{CombatStats, GameLog, LogEntry, Position, Viewshed},
+    consts::SPRITE_SIZE,
+    faction::PlayerHostility,
+    input::{Action, ActionInput},
+    map::Map,
+    player::{player_move, Player, PlayerMoveEvent, Resting},
+    ui::CursorGrid,
+    GameState,
+};
+
+//自动行走时每一步的间隔
+pub const TRAVEL_STEP_SECONDS: f32 = 0.1;
+
+//路径预览的颜色
+pub const PATH_PREVIEW_COLOR: Color = Color::rgba(1.0, 1.0, 0.0, 0.6);
+
+//点击地图上的格子，沿着寻到的路走过去
+#[derive(Event)]
+pub struct TravelEvent(pub Point);
+
+//玩家正在沿着路径行走，每次计时结束走一步
+#[derive(Debug, Component)]
+#[component(storage = "SparseSet")]
+pub struct Travel {
+    //还没走的格子，第一个是下一步
+    pub path: Vec<Point>,
+    pub timer: Timer,
+    //出发时的生命，受伤就停下
+    pub hp: i32,
+    //出发时已经看到的敌人，新的敌人出现就停下
+    pub seen: Vec<Entity>,
+    //自动探索，每走一步重新找最近的未探索格子
+    pub explore: bool,
+}
+
+impl Travel {
+    pub fn new(path: Vec<Point>, hp: i32, seen: Vec<Entity>) -> Self {
+        Travel {
+            path,
+            timer: Timer::from_seconds(TRAVEL_STEP_SECONDS, TimerMode::Repeating),
+            hp,
+            seen,
+            explore: false,
+        }
+    }
+
+    pub fn explore(hp: i32, seen: Vec<Entity>) -> Self {
+        Travel {
+            explore: true,
+            ..Travel::new(vec![], hp, seen)
+        }
+    }
+}
+
+//只在探索过的格子上寻路，路上的生物和门都不算阻挡
+struct RevealedMap<'a>(&'a Map);
+
+impl<'a> RevealedMap<'a> {
+    fn is_exit_valid(&self, x: i32, y: i32) -> bool {
+        let point = Point::new(x, y);
+
+        if !self.0.in_bounds(point) {
+            return false;
+        }
+
+        let idx = self.0.point2d_to_index(point);
+
+        self.0.revealed_tiles[idx] && self.0.tiles[idx].is_walkable()
+    }
+}
+
+impl<'a> Algorithm2D for RevealedMap<'a> {
+    fn dimensions(&self) -> Point {
+        self.0.dimensions()
+    }
+}
+
+impl<'a> BaseMap for RevealedMap<'a> {
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.0.get_pathing_distance(idx1, idx2)
+    }
+
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        let mut exits = SmallVec::new();
+        let point = self.0.index_to_point2d(idx);
+
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                if (dx, dy) == (0, 0) || !self.is_exit_valid(point.x + dx, point.y + dy) {
+                    continue;
+                }
+
+                let exit = self.0.xy_idx(point.x + dx, point.y + dy);
+                let base = if dx != 0 && dy != 0 { 1.45 } else { 1.0 };
+
+                exits.push((exit, base * self.0.tiles[exit].movement_cost()));
+            }
+        }
+
+        exits
+    }
+}
+
+//从起点到终点经过已探索格子的路径，不包含起点
+pub fn find_travel_path(map: &Map, start: Point, end: Point) -> Option<Vec<Point>> {
+    if start == end || !map.in_bounds(end) {
+        return None;
+    }
+
+    let end_idx = map.point2d_to_index(end);
+
+    if !map.revealed_tiles[end_idx] || !map.tiles[end_idx].is_walkable() {
+        return None;
+    }
+
+    let path = a_star_search(map.point2d_to_index(start), end_idx, &RevealedMap(map));
+
+    if !path.success {
+        return None;
+    }
+
+    Some(
+        path.steps
+            .iter()
+            .skip(1)
+            .map(|idx| map.index_to_point2d(*idx))
+            .collect(),
+    )
+}
+
+//朝最近的未探索格子或者看到过的物品走一步，没有能去的地方时返回None
+pub fn explore_step(map: &Map, start: Point) -> Option<Point> {
+    let start_idx = map.point2d_to_index(start);
+
+    let targets: Vec<usize> = (0..map.tiles.len())
+        .filter(|idx| *idx != start_idx && (!map.revealed_tiles[*idx] || map.items[*idx].is_some()))
+        .collect();
+
+    if targets.is_empty() {
+        return None;
+    }
+
+    let revealed = RevealedMap(map);
+    let max_depth = (map.width * map.height) as f32;
+    let dijkstra = DijkstraMap::new(map.width, map.height, &targets, &revealed, max_depth);
+
+    if dijkstra.map[start_idx] == f32::MAX {
+        return None;
+    }
+
+    DijkstraMap::find_lowest_exit(&dijkstra, start_idx, &revealed)
+        .map(|exit| map.index_to_point2d(exit))
+}
+
+pub struct TravelPlugin;
+
+impl Plugin for TravelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TravelEvent>();
+
+        app.add_systems(
+            Update,
+            (player_travel, start_travel, start_explore, path_preview)
+                .chain()
+                .before(player_move)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+fn start_travel(
+    mut commands: Commands,
+    mut travel_er: EventReader<TravelEvent>,
+    q_player: Query<(Entity, &Position, &Viewshed, &CombatStats), With<Player>>,
+    map: Res<Map>,
+    hostility: PlayerHostility,
+    mut log: ResMut<GameLog>,
+) {
+    let Some(TravelEvent(target)) = travel_er.read().last() else {
+        return;
+    };
+
+    let Ok((entity, position, viewshed, stats)) = q_player.get_single() else {
+        return;
+    };
+
+    let start = Point::new(position.x, position.y);
+
+    if start == *target {
+        return;
+    }
+
+    let Some(path) = find_travel_path(&map, start, *target) else {
+        log.add(LogEntry::system().text("You don't know a way there."));
+        return;
+    };
+
+    let seen = map.enemies_in_view(&viewshed.visible_tiles, |entity| {
+        hostility.is_hostile(entity)
+    });
+
+    commands
+        .entity(entity)
+        .remove::<Resting>()
+        .insert(Travel::new(path, stats.hp, seen));
+}
+
+//开始自动探索
+fn start_explore(
+    mut commands: Commands,
+    actions: ActionInput,
+    q_player: Query<(Entity, &Viewshed, &CombatStats), With<Player>>,
+    map: Res<Map>,
+    hostility: PlayerHostility,
+) {
+    if !actions.just_pressed(Action::Explore) {
+        return;
+    }
+
+    let Ok((entity, viewshed, stats)) = q_player.get_single() else {
+        return;
+    };
+
+    let seen = map.enemies_in_view(&viewshed.visible_tiles, |entity| {
+        hostility.is_hostile(entity)
+    });
+
+    commands
+        .entity(entity)
+        .remove::<Resting>()
+        .insert(Travel::explore(stats.hp, seen));
+}
+
+//沿着路径每回合走一步，看到新的敌人或者受伤时停下
+fn player_travel(
+    mut commands: Commands,
+    actions: ActionInput,
+    mut q_player: Query<(Entity, &Position, &mut Travel, &Viewshed, &CombatStats), With<Player>>,
+    map: Res<Map>,
+    hostility: PlayerHostility,
+    time: Res<Time>,
+    mut move_ew: EventWriter<PlayerMoveEvent>,
+    mut log: ResMut<GameLog>,
+) {
+    let Ok((entity, position, mut travel, viewshed, stats)) = q_player.get_single_mut() else {
+        return;
+    };
+
+    //按下任意按键时停下
+    if actions.any_just_pressed() {
+        commands.entity(entity).remove::<Travel>();
+        return;
+    }
+
+    if map
+        .enemies_in_view(&viewshed.visible_tiles, |entity| {
+            hostility.is_hostile(entity)
+        })
+        .iter()
+        .any(|enemy| !travel.seen.contains(enemy))
+    {
+        log.add(LogEntry::system().text("You stop, an enemy comes into view."));
+        commands.entity(entity).remove::<Travel>();
+        return;
+    }
+
+    if stats.hp < travel.hp {
+        log.add(LogEntry::system().text("You stop, you are hurt."));
+        commands.entity(entity).remove::<Travel>();
+        return;
+    }
+
+    //回血之后再受伤也要停下
+    travel.hp = stats.hp;
+
+    let current = Point::new(position.x, position.y);
+
+    if travel.path.first() == Some(&current) {
+        travel.path.remove(0);
+    }
+
+    if travel.explore && travel.path.is_empty() {
+        let Some(next) = explore_step(&map, current) else {
+            log.add(LogEntry::system().text("There is nothing left to explore."));
+            commands.entity(entity).remove::<Travel>();
+            return;
+        };
+
+        travel.path.push(next);
+    }
+
+    let Some(next) = travel.path.first().copied() else {
+        commands.entity(entity).remove::<Travel>();
+        return;
+    };
+
+    let delta = next - current;
+
+    //被传送走了，或者路被生物挡住了
+    let idx = map.xy_idx(next.x, next.y);
+
+    if delta.x.abs() > 1 || delta.y.abs() > 1 || (map.blocked[idx] && map.doors[idx].is_none()) {
+        commands.entity(entity).remove::<Travel>();
+        return;
+    }
+
+    travel.timer.tick(time.delta());
+
+    if travel.timer.just_finished() {
+        move_ew.send(PlayerMoveEvent(delta));
+    }
+}
+
+//鼠标悬停时显示走过去的路径，行走时显示剩下的路径
+fn path_preview(
+    cursor_grid: CursorGrid,
+    q_player: Query<(&Position, Option<&Travel>), With<Player>>,
+    map: Res<Map>,
+    mut gizmos: Gizmos,
+    mut preview: Local<Option<(Point, Point, Vec<Point>)>>,
+) {
+    let Ok((position, travel)) = q_player.get_single() else {
+        return;
+    };
+
+    let start = Point::new(position.x, position.y);
+
+    let path = if let Some(travel) = travel {
+        &travel.path
+    } else {
+        let Some(target) = cursor_grid.position() else {
+            return;
+        };
+
+        let target = Point::new(target.x, target.y);
+
+        //起点和终点都没变时不用重新寻路
+        let cached = matches!(&*preview, Some((s, t, _)) if *s == start && *t == target);
+
+        if !cached {
+            let path = find_travel_path(&map, start, target).unwrap_or_default();
+            *preview = Some((start, target, path));
+        }
+
+        &preview.as_ref().unwrap().2
+    };
+
+    let size = Vec2::new(SPRITE_SIZE[0] as f32, SPRITE_SIZE[1] as f32) - 2.0;
+
+    for point in path.iter() {
+        if let Some(center) = cursor_grid.world_position(*point) {
+            gizmos.rect_2d(center, 0.0, size, PATH_PREVIEW_COLOR);
+        }
+    }
+}
@@ -139,6 +139,12 @@ impl<'b, 'w> UiWidght<BackPackUiStateItem<'b, 'w>> for ItemUiData {
 
         let (_, res) = ui.allocate_exact_size(size, egui::Sense::click());
 
+        //用手柄选中时画出边框
+        if res.has_focus() {
+            ui.painter()
+                .rect_stroke(res.rect, 2.0, ui.visuals().selection.stroke);
+        }
+
         if res.clicked() {
             if let Some(item) = self.get_item() {
                 context.item.item_ew.send(ItemApplyEvent {
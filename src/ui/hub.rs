@@ -2,12 +2,18 @@ use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_egui::egui;
 
 use crate::{
-    common::{CombatStats, GameLog},
+    common::{CombatStats, GameLog, LogEntry, RangedWeapon},
     core::prelude::*,
+    hunger::{HungerClock, HungerState},
     player::Player,
     AppState,
 };
 
+use super::log_entry_job;
+
+//底部面板显示的日志条数，完整的日志在日志窗口里
+pub const HUD_LOG_LINES: usize = 4;
+
 pub struct HudPlugin;
 
 impl Plugin for HudPlugin {
@@ -22,6 +28,8 @@ impl Plugin for HudPlugin {
 #[derive(SystemParam)]
 pub struct HudParams<'w, 's> {
     q_stats: Query<'w, 's, &'static CombatStats, With<Player>>,
+    q_hunger: Query<'w, 's, &'static HungerClock, With<Player>>,
+    q_weapon: Query<'w, 's, &'static RangedWeapon, With<Player>>,
     game_log: Res<'w, GameLog>,
 }
 
@@ -35,32 +43,41 @@ where
     fn extra_ui_state(item: &<Self as SystemParam>::Item<'_, '_>) -> Self::UiState {
         let stats = item.q_stats.single();
 
-        let length = item.game_log.entries.len();
+        let logs = item.game_log.recent(HUD_LOG_LINES).cloned().collect();
 
-        let mut logs = vec![];
+        let hunger = item
+            .q_hunger
+            .get_single()
+            .map(|clock| clock.state)
+            .unwrap_or(HungerState::Normal);
 
-        if length <= 4 {
-            for i in 0..length {
-                logs.push(item.game_log.entries[i].clone());
-            }
-        } else {
-            for i in length - 4..length {
-                logs.push(item.game_log.entries[i].clone());
-            }
-        }
+        let ammo = item.q_weapon.get_single().ok().map(|weapon| weapon.ammo);
 
         HudUiState {
             logs,
             hp: stats.hp,
             max_hp: stats.max_hp,
+            hunger,
+            ammo,
         }
     }
 }
 
 pub struct HudUiState {
-    logs: Vec<String>,
+    logs: Vec<LogEntry>,
     hp: i32,
     max_hp: i32,
+    hunger: HungerState,
+    ammo: Option<i32>,
+}
+
+fn hunger_color(hunger: HungerState) -> egui::Color32 {
+    match hunger {
+        HungerState::WellFed => egui::Color32::GREEN,
+        HungerState::Normal => egui::Color32::WHITE,
+        HungerState::Hungry => egui::Color32::from_rgb(255, 165, 0),
+        HungerState::Starving => egui::Color32::RED,
+    }
 }
 
 impl<'w, 's> UiContainer<HudParams<'w, 's>> for HudUiState
@@ -79,7 +96,7 @@ where
                 ui.columns(2, |columns| {
                     egui::ScrollArea::vertical().show(&mut columns[0], |ui| {
                         for log in self.logs.iter() {
-                            ui.label(log);
+                            ui.label(log_entry_job(log, ui.style(), false));
                         }
                     });
 
@@ -91,6 +108,12 @@ where
 
                             ui.add(egui::ProgressBar::new(progress));
                         });
+
+                        ui.colored_label(hunger_color(self.hunger), self.hunger.to_string());
+
+                        if let Some(ammo) = self.ammo {
+                            ui.label(format!("Arrows: {}", ammo));
+                        }
                     });
                 });
             });
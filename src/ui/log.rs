@@ -0,0 +1,144 @@
+use bevy::{prelude::*, utils::HashSet};
+use bevy_egui::{
+    egui::{self, text::LayoutJob},
+    EguiContexts,
+};
+
+use crate::{
+    common::{GameLog, LogCategory, LogEntry, LogStyle},
+    input::{Action, ActionInput},
+    AppState, GameState,
+};
+
+//日志窗口是否打开，以及被过滤掉的分类
+#[derive(Resource, Default)]
+pub struct LogWindow {
+    pub open: bool,
+    pub hidden: HashSet<LogCategory>,
+}
+
+impl LogWindow {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+}
+
+pub struct LogWindowPlugin;
+
+impl Plugin for LogWindowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LogWindow>();
+
+        app.add_systems(
+            Update,
+            toggle_log_window
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Tab))),
+        );
+
+        app.add_systems(
+            Update,
+            show_log_window.run_if(in_state(AppState::InGame).and_then(log_window_open)),
+        );
+
+        app.add_systems(OnExit(AppState::InGame), close_log_window);
+    }
+}
+
+fn log_window_open(log_window: Res<LogWindow>) -> bool {
+    log_window.open
+}
+
+fn close_log_window(mut log_window: ResMut<LogWindow>) {
+    log_window.open = false;
+}
+
+fn toggle_log_window(
+    actions: ActionInput,
+    game_state: Res<State<GameState>>,
+    mut log_window: ResMut<LogWindow>,
+) {
+    if actions.just_pressed(Action::MessageLog) {
+        log_window.toggle();
+    } else if *game_state.get() == GameState::Playing && actions.just_pressed(Action::Cancel) {
+        log_window.open = false;
+    }
+}
+
+fn style_color(style: LogStyle) -> egui::Color32 {
+    match style {
+        LogStyle::Plain => egui::Color32::LIGHT_GRAY,
+        LogStyle::Name => egui::Color32::YELLOW,
+        LogStyle::Number => egui::Color32::LIGHT_BLUE,
+        LogStyle::Good => egui::Color32::GREEN,
+        LogStyle::Bad => egui::Color32::from_rgb(255, 96, 96),
+    }
+}
+
+//把一条日志排成带颜色的文字，show_turn时在前面加上回合数
+pub fn log_entry_job(entry: &LogEntry, style: &egui::Style, show_turn: bool) -> LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(style);
+    let mut job = LayoutJob::default();
+
+    let format = |color| egui::TextFormat {
+        font_id: font_id.clone(),
+        color,
+        ..Default::default()
+    };
+
+    if show_turn {
+        job.append(
+            &format!("[{}] ", entry.turn),
+            0.0,
+            format(egui::Color32::GRAY),
+        );
+    }
+
+    for span in entry.spans.iter() {
+        job.append(&span.text, 0.0, format(style_color(span.style)));
+    }
+
+    job
+}
+
+fn show_log_window(
+    mut contexts: EguiContexts,
+    game_log: Res<GameLog>,
+    mut log_window: ResMut<LogWindow>,
+) {
+    let mut open = log_window.open;
+
+    egui::Window::new("Message Log")
+        .open(&mut open)
+        .default_size([480.0, 320.0])
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                for category in LogCategory::ALL.iter() {
+                    let mut shown = !log_window.hidden.contains(category);
+
+                    if ui.checkbox(&mut shown, category.to_string()).changed() {
+                        if shown {
+                            log_window.hidden.remove(category);
+                        } else {
+                            log_window.hidden.insert(*category);
+                        }
+                    }
+                }
+            });
+
+            ui.separator();
+
+            egui::ScrollArea::vertical()
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    for entry in game_log
+                        .entries()
+                        .filter(|entry| !log_window.hidden.contains(&entry.category))
+                    {
+                        ui.label(log_entry_job(entry, ui.style(), true));
+                    }
+                });
+        });
+
+    log_window.open = open;
+}
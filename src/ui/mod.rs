@@ -1,36 +1,44 @@
 mod backpack;
 mod hub;
+mod log;
 mod player;
 mod tooltip;
 
 pub use backpack::*;
 use bevy::prelude::*;
 use hub::HudPlugin;
+use log::LogWindowPlugin;
+pub use log::{log_entry_job, LogWindow};
 
 use player::PlayerUIPlugin;
+pub use tooltip::CursorGrid;
 use tooltip::TooltipsPlugin;
 
-use crate::{state::AppStateManager, GameState};
+use crate::{
+    input::{Action, ActionInput},
+    state::AppStateManager,
+    GameState,
+};
 
 pub struct InternalUiPlugin;
 
 impl Plugin for InternalUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((HudPlugin, TooltipsPlugin, PlayerUIPlugin));
+        app.add_plugins((HudPlugin, TooltipsPlugin, PlayerUIPlugin, LogWindowPlugin));
 
         app.add_systems(Update, show_tab.run_if(in_state(GameState::Playing)));
         app.add_systems(Update, close_tab.run_if(in_state(GameState::Tab)));
     }
 }
 
-fn show_tab(keyboard_input: Res<ButtonInput<KeyCode>>, mut app_state_manager: AppStateManager) {
-    if keyboard_input.just_pressed(KeyCode::Tab) {
+fn show_tab(actions: ActionInput, mut app_state_manager: AppStateManager) {
+    if actions.just_pressed(Action::OpenInventory) {
         app_state_manager.start_tab();
     }
 }
 
-fn close_tab(keyboard_input: Res<ButtonInput<KeyCode>>, mut app_state_manager: AppStateManager) {
-    if keyboard_input.just_pressed(KeyCode::Tab) {
+fn close_tab(actions: ActionInput, mut app_state_manager: AppStateManager) {
+    if actions.just_pressed(Action::OpenInventory) || actions.just_pressed(Action::Cancel) {
         app_state_manager.start_playing();
     }
 }
@@ -1,4 +1,4 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::{ecs::system::SystemParam, prelude::*, window::PrimaryWindow};
 use bevy_egui::{egui, EguiContexts};
 use bracket_pathfinding::prelude::Point;
 
@@ -6,57 +6,98 @@ use crate::{
     common::{CombatStats, Position, Viewshed},
     consts::SPRITE_SIZE,
     enemy::Enemy,
+    input::{Action, ActionInput},
     loading::MainCamera,
     map::MapInstance,
     player::Player,
     state::AppStateManager,
+    trap::{Hidden, Trap, TrapType},
+    travel::TravelEvent,
     GameState,
 };
 
 #[derive(Resource, Default)]
 pub struct ToolTipEntity(pub Option<Entity>);
 
-fn update_tooltip(
+//鼠标位置和地图格子之间的转换
+#[derive(SystemParam)]
+pub struct CursorGrid<'w, 's> {
     // need to get window dimensions
-    wnds: Query<&Window, With<PrimaryWindow>>,
+    wnds: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    // query to get camera transform
+    q_camera: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<MainCamera>>,
+    q_map: Query<'w, 's, &'static GlobalTransform, With<MapInstance>>,
+}
+
+impl<'w, 's> CursorGrid<'w, 's> {
+    //鼠标所在的格子，鼠标不在窗口里时返回None
+    pub fn position(&self) -> Option<Position> {
+        // get the primary window
+        let wnd = self.wnds.get_single().ok()?;
+
+        // check if the cursor is in the primary window
+        let pos = wnd.cursor_position()?;
+
+        self.grid_position(pos)
+    }
+
+    //窗口上的一点对应的格子，触摸时也用这个转换
+    pub fn grid_position(&self, pos: Vec2) -> Option<Position> {
+        // assuming there is exactly one main camera entity, so this is OK
+        let (camera, camera_transform) = self.q_camera.get_single().ok()?;
+
+        let map_wld = self.q_map.get_single().ok()?.translation().truncate();
+
+        // apply the camera transform
+        let point_wld = camera.viewport_to_world_2d(camera_transform, pos)?;
+
+        // transform world coordinates to our grid
+        let grid_x =
+            (point_wld.x - map_wld.x + SPRITE_SIZE[0] as f32 / 2.0) / SPRITE_SIZE[0] as f32;
+        let grid_y =
+            (point_wld.y - map_wld.y + SPRITE_SIZE[0] as f32 / 2.0) / SPRITE_SIZE[1] as f32;
+
+        Some(Position {
+            x: grid_x.floor() as i32,
+            y: grid_y.floor() as i32,
+        })
+    }
+
+    //格子中心的世界坐标
+    pub fn world_position(&self, point: Point) -> Option<Vec2> {
+        let map_wld = self.q_map.get_single().ok()?.translation().truncate();
+
+        Some(Vec2::new(
+            map_wld.x + (point.x * SPRITE_SIZE[0] as i32) as f32,
+            map_wld.y + (point.y * SPRITE_SIZE[1] as i32) as f32,
+        ))
+    }
+}
+
+fn update_tooltip(
     // to get the mouse clicks
     buttons: Res<ButtonInput<MouseButton>>,
-    // query to get camera transform
-    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    cursor_grid: CursorGrid,
     // query to get all the entities with Name component
     q_names: Query<(&Position, Entity), With<Enemy>>,
+    // query to get the traps the player has already found
+    q_traps: Query<(&Position, Entity), (With<Trap>, Without<Hidden>)>,
     // query to get the player field of view
     player_fov_q: Query<&Viewshed, With<Player>>,
-    q_map: Query<&GlobalTransform, With<MapInstance>>,
+    game_state: Res<State<GameState>>,
+    mut travel_ew: EventWriter<TravelEvent>,
     mut app_state_manager: AppStateManager,
     mut tooltip_entity: ResMut<ToolTipEntity>,
+    mut contexts: EguiContexts,
 ) {
+    // clicks on egui windows and panels are not meant for the map
+    if contexts.ctx_mut().is_pointer_over_area() {
+        return;
+    }
+
     // if the user left clicks
     if buttons.just_pressed(MouseButton::Left) {
-        // get the primary window
-        let wnd = wnds.get_single().unwrap();
-
-        // check if the cursor is in the primary window
-        if let Some(pos) = wnd.cursor_position() {
-            // assuming there is exactly one main camera entity, so this is OK
-            let (camera, camera_transform) = q_camera.single();
-
-            let map_wld = q_map.single().translation().truncate();
-
-            // apply the camera transform
-            let point_wld = camera.viewport_to_world_2d(camera_transform, pos).unwrap();
-
-            // transform world coordinates to our grid
-            let grid_x =
-                (point_wld.x - map_wld.x + SPRITE_SIZE[0] as f32 / 2.0) / SPRITE_SIZE[0] as f32;
-            let grid_y =
-                (point_wld.y - map_wld.y + SPRITE_SIZE[0] as f32 / 2.0) / SPRITE_SIZE[1] as f32;
-
-            let grid_position = Position {
-                x: grid_x as i32,
-                y: grid_y as i32,
-            };
-
+        if let Some(grid_position) = cursor_grid.position() {
             // now we go through all the entities with name to see which one is the nearest
             // some variables placeholders to save the entity name and its health
             let mut good_click = false;
@@ -78,10 +119,29 @@ fn update_tooltip(
                     tooltip_entity_tmp = Some(entity);
                 });
 
+            // revealed traps are described too, enemies standing on them take priority
+            if !good_click {
+                q_traps
+                    .iter()
+                    .filter(|(pos, _)| {
+                        **pos == grid_position
+                            && player_fov
+                                .visible_tiles
+                                .contains(&(Point::new(grid_position.x, grid_position.y)))
+                    })
+                    .for_each(|(_, entity)| {
+                        good_click = true;
+                        tooltip_entity_tmp = Some(entity);
+                    });
+            }
+
             if good_click {
                 app_state_manager.start_tootip();
 
                 tooltip_entity.0 = tooltip_entity_tmp;
+            } else if *game_state.get() == GameState::Playing {
+                //点在其他格子上时走过去
+                travel_ew.send(TravelEvent(Point::new(grid_position.x, grid_position.y)));
             }
         }
     }
@@ -90,6 +150,7 @@ fn update_tooltip(
 fn show_tooltip(
     tooltip_entity: ResMut<ToolTipEntity>,
     q_enemy: Query<(&CombatStats, &Name)>,
+    q_traps: Query<&TrapType>,
     mut contexts: EguiContexts,
 ) {
     let entity = tooltip_entity.0.clone().unwrap();
@@ -98,14 +159,16 @@ fn show_tooltip(
         egui::show_tooltip(contexts.ctx_mut(), egui::Id::new("my_tooltip"), |ui| {
             ui.label(format!("{} {}:{}", name, stats.hp, stats.max_hp));
         });
+    } else if let Ok(trap_type) = q_traps.get(entity) {
+        egui::show_tooltip(contexts.ctx_mut(), egui::Id::new("my_tooltip"), |ui| {
+            ui.label(trap_type.to_string());
+            ui.label(trap_type.description());
+        });
     }
 }
 
-fn change_to_playing(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut app_state_manager: AppStateManager,
-) {
-    if keyboard_input.just_pressed(KeyCode::Escape) || keyboard_input.just_pressed(KeyCode::Space) {
+fn change_to_playing(actions: ActionInput, mut app_state_manager: AppStateManager) {
+    if actions.just_pressed(Action::Cancel) || actions.just_pressed(Action::Confirm) {
         app_state_manager.start_playing();
     }
 }
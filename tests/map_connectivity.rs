@@ -0,0 +1,81 @@
+//在大量随机种子上检查生成的地图都能从出生点走到所有地方
+use bracket_pathfinding::prelude::Point;
+use bracket_random::prelude::RandomNumberGenerator;
+use roguelike_tutorial::map::{
+    new_map_from_prefab, new_map_rooms_and_corridors, random_map, TileType, GOBLIN_CAMP,
+};
+
+const SEEDS: u64 = 5000;
+
+#[test]
+fn random_maps_are_connected() {
+    for seed in 0..SEEDS {
+        let map = random_map(&mut RandomNumberGenerator::seeded(seed));
+        let report = map.connectivity();
+
+        assert!(
+            report.is_connected(),
+            "seed {} has {} disconnected regions",
+            seed,
+            report.regions.len()
+        );
+        assert!(
+            report.unreachable_rooms.is_empty(),
+            "seed {} has unreachable rooms {:?}",
+            seed,
+            report.unreachable_rooms
+        );
+        assert!(map.has_closed_edges(), "seed {} is open at the edge", seed);
+    }
+}
+
+//所有生成方法都失败时使用的预制地图本身必须是连通的
+#[test]
+fn fallback_prefab_map_is_connected() {
+    let map = new_map_from_prefab(&GOBLIN_CAMP);
+
+    assert!(map.connectivity().is_connected());
+    assert!(map.has_closed_edges());
+}
+
+//出生点在墙上时不算连通，也不能修复
+#[test]
+fn start_on_wall_is_not_connected() {
+    let mut map = new_map_from_prefab(&GOBLIN_CAMP);
+    map.start = Point::new(0, 0);
+
+    assert_eq!(map.tiles[map.xy_idx(0, 0)], TileType::Wall);
+    assert!(!map.connectivity().is_connected());
+    assert!(!map.repair_connectivity());
+}
+
+#[test]
+fn repair_reconnects_isolated_regions() {
+    for seed in 0..SEEDS {
+        let mut map = new_map_rooms_and_corridors(&mut RandomNumberGenerator::seeded(seed));
+
+        //找一块四周都是墙的格子挖成地板，得到一个走不到的区域
+        let pocket = (1..map.height - 1)
+            .flat_map(|y| (1..map.width - 1).map(move |x| (x, y)))
+            .find(|(x, y)| {
+                (-1..=1).all(|dy| {
+                    (-1..=1).all(|dx| map.tiles[map.xy_idx(x + dx, y + dy)] == TileType::Wall)
+                })
+            });
+
+        let Some((x, y)) = pocket else {
+            continue;
+        };
+
+        let idx = map.xy_idx(x, y);
+        map.tiles[idx] = TileType::Floor;
+
+        assert!(!map.connectivity().is_connected(), "seed {}", seed);
+        assert!(
+            map.repair_connectivity(),
+            "seed {} could not be repaired",
+            seed
+        );
+        assert!(map.connectivity().reachable[idx], "seed {}", seed);
+    }
+}